@@ -0,0 +1,396 @@
+use crate::ui::MenuItem;
+
+/// Everything that can be bound to a key or run from the command palette.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Action {
+    ViewComments,
+    OpenArticle,
+    Quit,
+    Down,
+    Up,
+    HalfPageDown,
+    HalfPageUp,
+    PageDown,
+    PageUp,
+    First,
+    Last,
+    PreviousTab,
+    NextTab,
+    Refresh,
+    ToggleHidden,
+    Sort,
+    Bookmark,
+    Tag,
+    Note,
+    Export,
+    ExportThread,
+    ReloadConfig,
+    CommandPalette,
+    Help,
+    Log,
+    DismissBanner,
+    Split,
+    Thread,
+    Collapse,
+    SwitchPane,
+}
+
+impl Action {
+    pub const ALL: &'static [Action] = &[
+        Action::ViewComments,
+        Action::OpenArticle,
+        Action::Quit,
+        Action::Down,
+        Action::Up,
+        Action::HalfPageDown,
+        Action::HalfPageUp,
+        Action::PageDown,
+        Action::PageUp,
+        Action::First,
+        Action::Last,
+        Action::PreviousTab,
+        Action::NextTab,
+        Action::Refresh,
+        Action::ToggleHidden,
+        Action::Sort,
+        Action::Bookmark,
+        Action::Tag,
+        Action::Note,
+        Action::Export,
+        Action::ExportThread,
+        Action::ReloadConfig,
+        Action::CommandPalette,
+        Action::Help,
+        Action::Log,
+        Action::DismissBanner,
+        Action::Split,
+        Action::Thread,
+        Action::Collapse,
+        Action::SwitchPane,
+    ];
+
+    /// The name used for the action in the `[keybindings]` section and the command palette.
+    pub fn name(self) -> &'static str {
+        match self {
+            Action::ViewComments => "view_comments",
+            Action::OpenArticle => "open_article",
+            Action::Quit => "quit",
+            Action::Down => "down",
+            Action::Up => "up",
+            Action::HalfPageDown => "half_page_down",
+            Action::HalfPageUp => "half_page_up",
+            Action::PageDown => "page_down",
+            Action::PageUp => "page_up",
+            Action::First => "first",
+            Action::Last => "last",
+            Action::PreviousTab => "previous_tab",
+            Action::NextTab => "next_tab",
+            Action::Refresh => "refresh",
+            Action::ToggleHidden => "toggle_hidden",
+            Action::Sort => "sort",
+            Action::Bookmark => "bookmark",
+            Action::Tag => "tag",
+            Action::Note => "note",
+            Action::Export => "export",
+            Action::ExportThread => "export_thread",
+            Action::ReloadConfig => "reload_config",
+            Action::CommandPalette => "command_palette",
+            Action::Help => "help",
+            Action::Log => "log",
+            Action::DismissBanner => "dismiss_banner",
+            Action::Split => "split",
+            Action::Thread => "thread",
+            Action::Collapse => "collapse",
+            Action::SwitchPane => "switch_pane",
+        }
+    }
+
+    /// Finds the action by its name. `left` and `right` are the names the tab actions had
+    /// before they were renamed.
+    pub fn from_name(name: &str) -> Option<Action> {
+        match name {
+            "left" => Some(Action::PreviousTab),
+            "right" => Some(Action::NextTab),
+            name => Action::ALL
+                .iter()
+                .copied()
+                .find(|action| action.name() == name),
+        }
+    }
+
+    pub fn default_keys(self) -> &'static str {
+        match self {
+            Action::ViewComments => "c",
+            Action::OpenArticle => "enter",
+            Action::Quit => "q, esc",
+            Action::Down => "j, arrow_down",
+            Action::Up => "k, arrow_up",
+            Action::HalfPageDown => "ctrl+d",
+            Action::HalfPageUp => "ctrl+u",
+            Action::PageDown => "ctrl+f, page_down",
+            Action::PageUp => "ctrl+b, page_up",
+            Action::First => "g g, home",
+            Action::Last => "G, end",
+            Action::PreviousTab => "h, arrow_left",
+            Action::NextTab => "l, arrow_right",
+            Action::Refresh => "r",
+            Action::ToggleHidden => "H",
+            Action::Sort => "s",
+            Action::Bookmark => "b",
+            Action::Tag => "t",
+            Action::Note => "n",
+            Action::Export => "e",
+            Action::ExportThread => "E",
+            Action::ReloadConfig => "R",
+            Action::CommandPalette => ":",
+            Action::Help => "?",
+            Action::Log => "L",
+            Action::DismissBanner => "x",
+            Action::Split => "ctrl+w s",
+            Action::Thread => "T",
+            Action::Collapse => "space",
+            Action::SwitchPane => "tab, ctrl+w w",
+        }
+    }
+
+    pub fn description(self) -> &'static str {
+        match self {
+            Action::ViewComments => "Open the comments of the selected post",
+            Action::OpenArticle => "Open the article of the selected post",
+            Action::Quit => "Quit",
+            Action::Down => "Select the next post",
+            Action::Up => "Select the previous post",
+            Action::HalfPageDown => "Move down half a page",
+            Action::HalfPageUp => "Move up half a page",
+            Action::PageDown => "Move down a page",
+            Action::PageUp => "Move up a page",
+            Action::First => "Select the first post, or the post with the rank typed before",
+            Action::Last => "Select the last post, or the post with the rank typed before",
+            Action::PreviousTab => "Go to the previous tab",
+            Action::NextTab => "Go to the next tab",
+            Action::Refresh => "Fetch the posts again",
+            Action::ToggleHidden => "Show or hide the posts muted by the filters",
+            Action::Sort => "Cycle the sort order",
+            Action::Bookmark => "Save or unsave the selected post",
+            Action::Tag => "Edit the tags of the selected saved post",
+            Action::Note => "Edit the note of the selected saved post",
+            Action::Export => "Export the posts of the tab",
+            Action::ExportThread => "Export the comment thread of the selected post",
+            Action::ReloadConfig => "Load the config file again",
+            Action::CommandPalette => "Run a command by name",
+            Action::Help => "Show every action and its keys",
+            Action::Log => "Show the messages and errors of the session",
+            Action::DismissBanner => "Close the warning about problems in the config",
+            Action::Split => "Show a second feed next to the first one, or close the second pane",
+            Action::Thread => "Show the comment thread of the selected post in a second pane",
+            Action::Collapse => "Hide or show the replies to the selected comment of the thread",
+            Action::SwitchPane => "Move the focus to the other pane",
+        }
+    }
+
+    /// Returns true if the action works on the selected post, so it can be given the rank of
+    /// the post to select in the command palette.
+    fn takes_rank(self) -> bool {
+        matches!(
+            self,
+            Action::ViewComments
+                | Action::OpenArticle
+                | Action::Bookmark
+                | Action::Tag
+                | Action::Note
+                | Action::ExportThread
+                | Action::Thread
+        )
+    }
+}
+
+/// A command entered in the command palette.
+#[derive(Debug, PartialEq)]
+pub enum Command {
+    /// Runs the action, after selecting the post with the given rank if there is one.
+    Run(Action, Option<usize>),
+    /// Selects the post with the rank.
+    Jump(usize),
+    Tab(MenuItem),
+    User(String),
+}
+
+/// Shorter names for the commands that take arguments, next to the names of the actions.
+const ALIASES: &[(&str, &str)] = &[
+    ("open", "open_article"),
+    ("comments", "view_comments"),
+    ("tab", "tab"),
+    ("user", "user"),
+];
+
+/// Returns the name of the command that best matches the possibly abbreviated name, like
+/// `tog` or `exth` for `toggle_hidden` and `export_thread`.
+pub fn complete(name: &str) -> Option<&'static str> {
+    let name = name.to_lowercase();
+    ALIASES
+        .iter()
+        .map(|(alias, _)| *alias)
+        .chain(Action::ALL.iter().map(|action| action.name()))
+        .filter_map(|candidate| fuzzy_score(&name, candidate).map(|score| (score, candidate)))
+        .min_by_key(|(score, _)| *score)
+        .map(|(_, candidate)| candidate)
+}
+
+/// Parses the input of the command palette, like `open 12`, `tab new`, `user pg` or `12` to
+/// jump to the post ranked 12.
+pub fn parse_command(input: &str) -> Result<Command, String> {
+    let mut words = input.split_whitespace();
+    let name = words
+        .next()
+        .ok_or_else(|| String::from("No command given"))?;
+    let argument = words.next();
+    if words.next().is_some() {
+        return Err(format!("{} takes at most one argument", name));
+    }
+
+    if let (Ok(rank), None) = (name.parse(), argument) {
+        return Ok(Command::Jump(rank));
+    }
+
+    let command = complete(name).ok_or_else(|| format!("Unknown command {}", name))?;
+    let command = ALIASES
+        .iter()
+        .find(|(alias, _)| *alias == command)
+        .map(|(_, command)| *command)
+        .unwrap_or(command);
+
+    match (command, argument) {
+        ("tab", Some(tab)) => tab.parse().map(Command::Tab),
+        ("user", Some(user)) => Ok(Command::User(user.to_string())),
+        ("tab", None) | ("user", None) => Err(format!("{} expects an argument", command)),
+        (command, argument) => {
+            let action =
+                Action::from_name(command).ok_or_else(|| format!("Unknown command {}", command))?;
+            match argument {
+                None => Ok(Command::Run(action, None)),
+                Some(rank) if action.takes_rank() => rank
+                    .parse()
+                    .map(|rank| Command::Run(action, Some(rank)))
+                    .map_err(|_| format!("{} is not a valid rank", rank)),
+                Some(_) => Err(format!("{} doesn't take an argument", command)),
+            }
+        }
+    }
+}
+
+/// Scores how well the pattern matches the candidate, lower is better. Exact matches come
+/// first, then prefixes, then candidates containing the characters of the pattern in order.
+fn fuzzy_score(pattern: &str, candidate: &str) -> Option<usize> {
+    if pattern == candidate {
+        return Some(0);
+    }
+    if candidate.starts_with(pattern) {
+        return Some(1 + candidate.len() - pattern.len());
+    }
+
+    let mut chars = candidate.char_indices();
+    let mut first = None;
+    let mut last = 0;
+    for pattern_char in pattern.chars() {
+        let (index, _) = chars.find(|(_, char)| *char == pattern_char)?;
+        first.get_or_insert(index);
+        last = index;
+    }
+
+    Some(100 + last - first.unwrap_or(0) + candidate.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::keys::Keybinding;
+
+    #[test]
+    fn names_and_default_keys_are_valid() {
+        for action in Action::ALL {
+            assert_eq!(Action::from_name(action.name()), Some(*action));
+            assert!(
+                Keybinding::parse(action.default_keys()).is_ok(),
+                "{}",
+                action.name()
+            );
+            assert!(!action.description().is_empty());
+        }
+        assert_eq!(Action::from_name("left"), Some(Action::PreviousTab));
+        assert_eq!(Action::from_name("nope"), None);
+    }
+
+    #[test]
+    fn scores_exact_matches_before_prefixes_before_subsequences() {
+        assert_eq!(fuzzy_score("sort", "sort"), Some(0));
+        let prefix = fuzzy_score("exp", "export").unwrap();
+        let subsequence = fuzzy_score("exth", "export_thread").unwrap();
+        assert!(0 < prefix && prefix < subsequence);
+        // Characters close together score better than spread out ones.
+        assert!(fuzzy_score("th", "thread") < fuzzy_score("th", "toggle_hidden"));
+        assert_eq!(fuzzy_score("xyz", "export"), None);
+        assert_eq!(fuzzy_score("ba", "abc"), None);
+    }
+
+    #[test]
+    fn completes_abbreviated_names() {
+        assert_eq!(complete("tog"), Some("toggle_hidden"));
+        assert_eq!(complete("exth"), Some("export_thread"));
+        assert_eq!(complete("open"), Some("open"));
+        assert_eq!(complete("TAB"), Some("tab"));
+        assert_eq!(complete("qzx"), None);
+    }
+
+    #[test]
+    fn parses_commands() {
+        assert_eq!(
+            parse_command("open 12"),
+            Ok(Command::Run(Action::OpenArticle, Some(12)))
+        );
+        assert_eq!(
+            parse_command("comments"),
+            Ok(Command::Run(Action::ViewComments, None))
+        );
+        assert_eq!(parse_command(" 12 "), Ok(Command::Jump(12)));
+        assert_eq!(parse_command("tab ask"), Ok(Command::Tab(MenuItem::Ask)));
+        assert_eq!(
+            parse_command("user pg"),
+            Ok(Command::User(String::from("pg")))
+        );
+        assert_eq!(
+            parse_command("refr"),
+            Ok(Command::Run(Action::Refresh, None))
+        );
+    }
+
+    #[test]
+    fn reports_invalid_commands() {
+        assert_eq!(parse_command(""), Err(String::from("No command given")));
+        assert_eq!(
+            parse_command("tab"),
+            Err(String::from("tab expects an argument"))
+        );
+        assert_eq!(
+            parse_command("tab foo"),
+            Err(String::from(
+                "foo is not a valid tab, expected top, new, ask, show, jobs or saved"
+            ))
+        );
+        assert_eq!(
+            parse_command("open x"),
+            Err(String::from("x is not a valid rank"))
+        );
+        assert_eq!(
+            parse_command("quit 3"),
+            Err(String::from("quit doesn't take an argument"))
+        );
+        assert_eq!(
+            parse_command("user a b"),
+            Err(String::from("user takes at most one argument"))
+        );
+        assert_eq!(
+            parse_command("qzx"),
+            Err(String::from("Unknown command qzx"))
+        );
+    }
+}
@@ -1,6 +1,8 @@
-use serde::Deserialize;
+use futures::{future::LocalBoxFuture, FutureExt};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use std::time::Instant;
 
-#[derive(Debug, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone)]
 #[serde(rename_all = "lowercase")]
 #[serde(tag = "type")]
 pub enum Post {
@@ -11,7 +13,98 @@ pub enum Post {
     PollOpt(PollOpt),
 }
 
-#[derive(Debug, Deserialize, Clone)]
+impl Post {
+    pub fn id(&self) -> u32 {
+        match self {
+            Post::Job(job) => job.id,
+            Post::Story(story) => story.id,
+            Post::Comment(comment) => comment.id,
+            Post::Poll(poll) => poll.id,
+            Post::PollOpt(poll_opt) => poll_opt.id,
+        }
+    }
+
+    pub fn by(&self) -> &str {
+        match self {
+            Post::Job(job) => &job.by,
+            Post::Story(story) => &story.by,
+            Post::Comment(comment) => &comment.by,
+            Post::Poll(poll) => &poll.by,
+            Post::PollOpt(poll_opt) => &poll_opt.by,
+        }
+    }
+
+    /// Returns the title of the post, or an empty string for comments and poll options.
+    pub fn title(&self) -> &str {
+        match self {
+            Post::Job(job) => &job.title,
+            Post::Story(story) => &story.title,
+            Post::Poll(poll) => &poll.title,
+            Post::Comment(_) | Post::PollOpt(_) => "",
+        }
+    }
+
+    pub fn time(&self) -> time::OffsetDateTime {
+        match self {
+            Post::Job(job) => job.time,
+            Post::Story(story) => story.time,
+            Post::Comment(comment) => comment.time,
+            Post::Poll(poll) => poll.time,
+            Post::PollOpt(poll_opt) => poll_opt.time,
+        }
+    }
+
+    /// Returns the score of the post, or 0 for posts that can't be voted on.
+    pub fn score(&self) -> u32 {
+        match self {
+            Post::Story(story) => story.score,
+            Post::Poll(poll) => poll.score,
+            Post::PollOpt(poll_opt) => poll_opt.score,
+            Post::Job(_) | Post::Comment(_) => 0,
+        }
+    }
+
+    /// Returns the total comment count of the post, or 0 for posts without comments.
+    pub fn descendants(&self) -> u32 {
+        match self {
+            Post::Story(story) => story.descendants,
+            Post::Poll(poll) => poll.descendants,
+            Post::Job(_) | Post::Comment(_) | Post::PollOpt(_) => 0,
+        }
+    }
+
+    /// Returns the HTML text of the post, or an empty string if it has none.
+    pub fn text(&self) -> &str {
+        match self {
+            Post::Job(job) => &job.text,
+            Post::Story(story) => &story.text,
+            Post::Comment(comment) => &comment.text,
+            Post::Poll(poll) => &poll.text,
+            Post::PollOpt(_) => "",
+        }
+    }
+
+    pub fn kids(&self) -> &[u32] {
+        match self {
+            Post::Job(job) => &job.kids,
+            Post::Story(story) => &story.kids,
+            Post::Comment(comment) => &comment.kids,
+            Post::Poll(poll) => &poll.kids,
+            Post::PollOpt(poll_opt) => &poll_opt.kids,
+        }
+    }
+
+    /// Returns the url of the post, or an empty string if the post doesn't link anywhere.
+    pub fn url(&self) -> &str {
+        match self {
+            Post::Job(job) => &job.url,
+            Post::Story(story) => &story.url,
+            Post::Comment(_) | Post::Poll(_) | Post::PollOpt(_) => "",
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct PollOpt {
     pub id: u32,
     #[serde(default)]
@@ -31,7 +124,7 @@ pub struct PollOpt {
     pub score: u32,
 }
 
-#[derive(Debug, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct Poll {
     pub id: u32,
     #[serde(default)]
@@ -57,7 +150,7 @@ pub struct Poll {
     pub text: String,
 }
 
-#[derive(Debug, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct Comment {
     pub id: u32,
     #[serde(default)]
@@ -77,7 +170,7 @@ pub struct Comment {
     pub text: String,
 }
 
-#[derive(Debug, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct Job {
     pub id: u32,
     #[serde(default)]
@@ -99,7 +192,7 @@ pub struct Job {
     pub title: String,
 }
 
-#[derive(Debug, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct Story {
     pub id: u32,
     #[serde(default)]
@@ -121,9 +214,11 @@ pub struct Story {
     pub title: String,
     #[serde(default)]
     pub url: String,
+    #[serde(default)]
+    pub text: String,
 }
 
-#[derive(Debug, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct User {
     pub id: String,
     #[serde(with = "time::serde::timestamp")]
@@ -148,13 +243,21 @@ impl Default for User {
     }
 }
 
+/// A post together with all of its replies.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Thread {
+    pub post: Post,
+    #[serde(default)]
+    pub replies: Vec<Thread>,
+}
+
 #[derive(Clone)]
 pub struct Client {
     pub client: reqwest::Client,
     pub url: String,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Serialize, Deserialize)]
 pub struct Updates {
     #[serde(default)]
     pub items: Vec<u32>,
@@ -169,9 +272,14 @@ pub struct Updates {
 #[allow(dead_code)]
 pub const BASE_URL: &str = "https://hacker-news.firebaseio.com/v0";
 
+/// Uses the API at the `HNTUI_API_URL` environment variable if it's set, or at `BASE_URL`.
 impl Default for Client {
     fn default() -> Self {
-        Self::new(BASE_URL.to_string(), reqwest::Client::new())
+        let url = std::env::var("HNTUI_API_URL").unwrap_or_else(|_| BASE_URL.to_string());
+        Self::new(
+            url.trim_end_matches('/').to_string(),
+            reqwest::Client::new(),
+        )
     }
 }
 
@@ -187,16 +295,73 @@ impl Client {
         Ok(self.client.get(url).send().await?)
     }
 
+    /// Fetches the JSON at the url, logging its status, latency and size.
+    async fn get_json<T: DeserializeOwned>(
+        &self,
+        url: &str,
+    ) -> Result<T, Box<dyn std::error::Error>> {
+        let start = Instant::now();
+        let response = match self.perform_request(url).await {
+            Ok(response) => response,
+            Err(err) => {
+                tracing::warn!(
+                    url,
+                    latency_ms = start.elapsed().as_millis() as u64,
+                    error = %err,
+                    "request failed"
+                );
+                return Err(err);
+            }
+        };
+
+        let status = response.status();
+        let body = response.bytes().await?;
+        tracing::debug!(
+            url,
+            status = status.as_u16(),
+            latency_ms = start.elapsed().as_millis() as u64,
+            bytes = body.len(),
+            "request"
+        );
+
+        serde_json::from_slice(&body).map_err(|err| {
+            tracing::warn!(url, status = status.as_u16(), error = %err, "invalid response");
+            err.into()
+        })
+    }
+
     pub async fn get_item_by_id(
         &self,
         id: u32,
         options: &str,
     ) -> Result<Post, Box<dyn std::error::Error>> {
-        Ok(self
-            .perform_request(&format!("{}/item/{}.json?{}", self.url, id, options))
-            .await?
-            .json::<Post>()
-            .await?)
+        self.get_json(&format!("{}/item/{}.json?{}", self.url, id, options))
+            .await
+    }
+
+    /// Fetches the post with all of its replies, up to `depth` levels deep.
+    pub fn get_thread(
+        &self,
+        id: u32,
+        depth: Option<usize>,
+    ) -> LocalBoxFuture<'_, Result<Thread, Box<dyn std::error::Error>>> {
+        async move {
+            let post = self.get_item_by_id(id, "").await?;
+
+            let replies = if depth == Some(0) {
+                Vec::new()
+            } else {
+                futures::future::try_join_all(
+                    post.kids()
+                        .iter()
+                        .map(|kid| self.get_thread(*kid, depth.map(|depth| depth - 1))),
+                )
+                .await?
+            };
+
+            Ok(Thread { post, replies })
+        }
+        .boxed_local()
     }
 
     pub async fn get_user_by_id(
@@ -204,81 +369,57 @@ impl Client {
         id: &str,
         options: &str,
     ) -> Result<User, Box<dyn std::error::Error>> {
-        Ok(self
-            .perform_request(&format!("{}/user/{}.json?{}", self.url, id, options))
-            .await?
-            .json::<User>()
-            .await?)
+        self.get_json(&format!("{}/user/{}.json?{}", self.url, id, options))
+            .await
     }
 
     pub async fn get_max_item_id(&self, options: &str) -> Result<u32, Box<dyn std::error::Error>> {
-        Ok(self
-            .perform_request(&format!("{}/maxitem.json?{}", self.url, options))
-            .await?
-            .json::<u32>()
-            .await?)
+        self.get_json(&format!("{}/maxitem.json?{}", self.url, options))
+            .await
     }
 
     pub async fn get_top_stories(
         &self,
         options: &str,
     ) -> Result<Vec<u32>, Box<dyn std::error::Error>> {
-        Ok(self
-            .perform_request(&format!("{}/topstories.json?{}", self.url, options))
-            .await?
-            .json::<Vec<u32>>()
-            .await?)
+        self.get_json(&format!("{}/topstories.json?{}", self.url, options))
+            .await
     }
 
     pub async fn get_new_stories(
         &self,
         options: &str,
     ) -> Result<Vec<u32>, Box<dyn std::error::Error>> {
-        Ok(self
-            .perform_request(&format!("{}/newstories.json?{}", self.url, options))
-            .await?
-            .json::<Vec<u32>>()
-            .await?)
+        self.get_json(&format!("{}/newstories.json?{}", self.url, options))
+            .await
     }
 
     pub async fn get_ask_stories(
         &self,
         options: &str,
     ) -> Result<Vec<u32>, Box<dyn std::error::Error>> {
-        Ok(self
-            .perform_request(&format!("{}/askstories.json?{}", self.url, options))
-            .await?
-            .json::<Vec<u32>>()
-            .await?)
+        self.get_json(&format!("{}/askstories.json?{}", self.url, options))
+            .await
     }
 
     pub async fn get_show_stories(
         &self,
         options: &str,
     ) -> Result<Vec<u32>, Box<dyn std::error::Error>> {
-        Ok(self
-            .perform_request(&format!("{}/showstories.json?{}", self.url, options))
-            .await?
-            .json::<Vec<u32>>()
-            .await?)
+        self.get_json(&format!("{}/showstories.json?{}", self.url, options))
+            .await
     }
 
     pub async fn get_job_stories(
         &self,
         options: &str,
     ) -> Result<Vec<u32>, Box<dyn std::error::Error>> {
-        Ok(self
-            .perform_request(&format!("{}/jobstories.json?{}", self.url, options))
-            .await?
-            .json::<Vec<u32>>()
-            .await?)
+        self.get_json(&format!("{}/jobstories.json?{}", self.url, options))
+            .await
     }
 
     pub async fn get_updates(&self, options: &str) -> Result<Updates, Box<dyn std::error::Error>> {
-        Ok(self
-            .perform_request(&format!("{}/updates.json?{}", self.url, options))
-            .await?
-            .json::<Updates>()
-            .await?)
+        self.get_json(&format!("{}/updates.json?{}", self.url, options))
+            .await
     }
-}
\ No newline at end of file
+}
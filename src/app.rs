@@ -0,0 +1,962 @@
+use crate::{
+    actions::{self, Action, Command},
+    bookmarks::Bookmarks,
+    config::Config,
+    draw::draw,
+    export::{self, Format},
+    guard::set_mouse_capture,
+    history::History,
+    items::{Change, FeedItems, SortMode},
+    keys::{self, PendingKeys},
+    logging,
+    notifications::{Level, Notifications},
+    pane::{FeedPane, Focus, Pane, ThreadPane},
+    session::{PaneState, SavedSession, Session, ThreadState},
+    ui::{self, MenuItem, Prompt},
+};
+use api::Post;
+use crossterm::{
+    event,
+    event::{KeyCode, MouseButton, MouseEvent, MouseEventKind},
+};
+use std::collections::{HashMap, HashSet};
+use std::{time::Duration, time::Instant};
+use time::OffsetDateTime;
+use tui::{backend::CrosstermBackend, layout::Rect, terminal::Terminal};
+
+/// Returns how the posts of the feed changed in its last refresh. Saved posts aren't refreshed.
+pub fn feed_changes(
+    feed: MenuItem,
+    items: &HashMap<MenuItem, FeedItems>,
+) -> Option<&HashMap<u32, Change>> {
+    items.get(&feed).map(|items| &items.changes)
+}
+
+pub enum PromptKind {
+    Tags(u32),
+    Note(u32),
+    Export,
+    ExportThread(u32),
+    Command,
+}
+
+/// The views that can be opened over the list.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Overlay {
+    Help,
+    Log,
+}
+
+pub type CrosstermTerminal = Terminal<CrosstermBackend<std::io::Stdout>>;
+
+/// Two clicks on the same post within this time open it.
+const DOUBLE_CLICK: Duration = Duration::from_millis(400);
+
+pub struct App {
+    pub config: Config,
+    client: api::Client,
+    /// The posts of every feed but the saved posts, which come from the bookmarks.
+    pub items: HashMap<MenuItem, FeedItems>,
+    pub history: History,
+    pub bookmarks: Bookmarks,
+    pub current_time: OffsetDateTime,
+    pub show_hidden: bool,
+    pub sort_mode: SortMode,
+    /// The feed of the first pane, which is always shown.
+    pub main: FeedPane,
+    /// The second pane, if the view is split.
+    pub split: Option<Pane>,
+    pub focus: Focus,
+    pub prompt: Option<Prompt<PromptKind>>,
+    pub pending_keys: PendingKeys,
+    /// Where the tabs were last drawn, to find what was clicked.
+    pub tabs_area: Rect,
+    /// When and on which post of which pane the last click was, to detect double clicks.
+    last_click: Option<(Instant, Focus, usize)>,
+    pub notifications: Notifications,
+    /// Whether posts are being fetched, to show it in the status bar.
+    pub loading: bool,
+    /// The overlay drawn over the list, if one is open, and how far it is scrolled.
+    pub overlay: Option<(Overlay, u16)>,
+    /// The session file, if the session is restored on start.
+    session: Option<Session>,
+}
+
+impl App {
+    pub fn new(
+        config: Config,
+        client: api::Client,
+        history: History,
+        bookmarks: Bookmarks,
+        session: Option<Session>,
+    ) -> App {
+        App {
+            items: MenuItem::ALL
+                .iter()
+                .filter(|feed| **feed != MenuItem::Saved)
+                .map(|feed| (*feed, FeedItems::new(client.clone(), *feed)))
+                .collect(),
+            client,
+            history,
+            bookmarks,
+            current_time: OffsetDateTime::now_utc(),
+            show_hidden: false,
+            sort_mode: SortMode::Rank,
+            main: FeedPane::new(config.default_view, config.scroll_past_list),
+            split: None,
+            focus: Focus::Main,
+            prompt: None,
+            pending_keys: PendingKeys::default(),
+            tabs_area: Rect::default(),
+            last_click: None,
+            notifications: Notifications::default(),
+            loading: false,
+            overlay: None,
+            session,
+            config,
+        }
+    }
+
+    /// Adds the problems found in the config to the log and shows a summary of them in the
+    /// banner, or closes the banner if there are none.
+    pub fn report_config_errors(&mut self) {
+        for error in &self.config.errors {
+            self.notifications
+                .add_to_log(Level::Warning, format!("Config: {}", error));
+        }
+        match config_warning(&self.config) {
+            Some(warning) => self.notifications.set_banner(warning),
+            None => self.notifications.dismiss_banner(),
+        }
+    }
+
+    /// Returns the pane with the feed the tabs and the feed actions apply to: the focused pane,
+    /// or the first pane if the focused one shows a thread.
+    pub fn feed(&self) -> &FeedPane {
+        self.feed_pane(self.focus)
+    }
+
+    fn feed_mut(&mut self) -> &mut FeedPane {
+        self.feed_pane_mut(self.focus)
+    }
+
+    /// Returns the feed of the pane, or of the first pane if the pane shows a thread.
+    pub fn feed_pane(&self, pane: Focus) -> &FeedPane {
+        match (pane, &self.split) {
+            (Focus::Split, Some(Pane::Feed(feed))) => feed,
+            _ => &self.main,
+        }
+    }
+
+    fn feed_pane_mut(&mut self, pane: Focus) -> &mut FeedPane {
+        match (pane, &mut self.split) {
+            (Focus::Split, Some(Pane::Feed(feed))) => feed,
+            _ => &mut self.main,
+        }
+    }
+
+    /// Returns the thread of the focused pane, if it shows one.
+    pub fn thread(&self) -> Option<&ThreadPane> {
+        match (self.focus, &self.split) {
+            (Focus::Split, Some(Pane::Thread(thread))) => Some(thread),
+            _ => None,
+        }
+    }
+
+    fn thread_mut(&mut self) -> Option<&mut ThreadPane> {
+        match (self.focus, &mut self.split) {
+            (Focus::Split, Some(Pane::Thread(thread))) => Some(thread),
+            _ => None,
+        }
+    }
+
+    /// Returns the panes that show a feed.
+    fn feed_panes(&self) -> Vec<Focus> {
+        match self.split {
+            Some(Pane::Feed(_)) => vec![Focus::Main, Focus::Split],
+            _ => vec![Focus::Main],
+        }
+    }
+
+    /// Loads the posts of the focused feed that aren't muted by the filters in the config and
+    /// sorts them. The feed is refetched from the API first if `refresh` is set.
+    /// Saved posts are never muted. Failures are shown as an error, keeping the posts that
+    /// were shown before.
+    pub async fn load(&mut self, refresh: bool) {
+        self.load_pane(self.feed_focus(), refresh).await
+    }
+
+    /// Returns the focused pane, or the first pane if the focused one shows a thread.
+    fn feed_focus(&self) -> Focus {
+        if self.feed_panes().contains(&self.focus) {
+            self.focus
+        } else {
+            Focus::Main
+        }
+    }
+
+    /// Loads the posts of every pane that shows a feed, like after changing the sort order.
+    async fn load_all(&mut self) {
+        for pane in self.feed_panes() {
+            self.load_pane(pane, false).await;
+        }
+    }
+
+    async fn load_pane(&mut self, pane: Focus, refresh: bool) {
+        self.load_feed(pane, self.feed_pane(pane).feed, refresh)
+            .await
+    }
+
+    /// Loads the feed into the pane like `load_pane`. The pane keeps showing its current feed
+    /// if the feed fails to load.
+    async fn load_feed(&mut self, pane: Focus, feed: MenuItem, refresh: bool) {
+        let start = Instant::now();
+        match self.try_load(pane, feed, refresh).await {
+            Ok(()) => tracing::info!(
+                feed = %feed,
+                refresh,
+                posts = self.feed_pane(pane).posts.len(),
+                hidden = self.feed_pane(pane).muted.len(),
+                elapsed_ms = start.elapsed().as_millis() as u64,
+                "loaded feed"
+            ),
+            Err(err) => self
+                .notifications
+                .error(format!("Couldn't load the {} posts: {}", feed, err)),
+        }
+    }
+
+    async fn try_load(
+        &mut self,
+        pane: Focus,
+        feed: MenuItem,
+        refresh: bool,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        if refresh {
+            self.current_time = OffsetDateTime::now_utc();
+            if let Some(items) = self.items.get_mut(&feed) {
+                items.refresh(self.config.max_items).await?;
+            }
+        }
+
+        let (mut posts, muted) = match self.items.get_mut(&feed) {
+            Some(items) => self.config.filters.apply(
+                items.get_vec(self.config.max_items).await?,
+                self.show_hidden,
+            ),
+            None => (self.bookmarks.get_vec(), HashSet::new()),
+        };
+        self.sort_mode.sort(&mut posts, self.current_time);
+
+        self.history.see(&posts, refresh);
+        self.write_history();
+
+        // Feeds open where they were left, or at the top the first time.
+        let position = self.feed_pane_mut(pane).show(feed, posts, muted);
+        self.update_list();
+
+        let feed_pane = self.feed_pane_mut(pane);
+        match position {
+            Some(position) => feed_pane.restore(position),
+            None => feed_pane.stateful_list.select_post_at_row(0, 0),
+        }
+
+        Ok(())
+    }
+
+    /// Loads the config file again and applies it, reporting the result in the status bar.
+    /// The posts are fetched again if `max_items` changed.
+    pub async fn reload_config(&mut self) {
+        let config = match self.config.reload() {
+            Ok(config) => config,
+            Err(err) => {
+                self.notifications.error(format!(
+                    "Couldn't reload {}: {}",
+                    self.config.path().display(),
+                    err
+                ));
+                self.config.ignore_change();
+                return;
+            }
+        };
+        if config.mouse != self.config.mouse {
+            if let Err(err) = set_mouse_capture(config.mouse) {
+                self.notifications
+                    .error(format!("Couldn't change the mouse capture: {}", err));
+            }
+        }
+        if config.log_level != self.config.log_level {
+            if let Err(err) = logging::init(config.log_level) {
+                self.notifications
+                    .error(format!("Couldn't open the log: {}", err));
+            }
+        }
+        if config.max_items != self.config.max_items {
+            for items in self.items.values_mut() {
+                items.clear();
+            }
+        }
+
+        for pane in self.feed_panes() {
+            self.feed_pane_mut(pane)
+                .stateful_list
+                .set_scroll_past_list(config.scroll_past_list);
+        }
+        if let Some(Pane::Thread(thread)) = &mut self.split {
+            // The thread is wrapped again with the new theme.
+            thread.width = 0;
+        }
+        self.config = config;
+        self.report_config_errors();
+        if self.config.errors.is_empty() {
+            self.notifications
+                .info(format!("Reloaded {}", self.config.path().display()));
+        }
+
+        self.load_all().await
+    }
+
+    /// Loads the posts like `load`, drawing the status bar as loading first as fetching them
+    /// from the API can take a while.
+    async fn fetch(
+        &mut self,
+        refresh: bool,
+        terminal: &mut CrosstermTerminal,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let feed = self.feed().feed;
+        self.fetch_feed(feed, refresh, terminal).await
+    }
+
+    /// Shows the feed in the focused feed pane, where it was left.
+    async fn switch_feed(
+        &mut self,
+        feed: MenuItem,
+        terminal: &mut CrosstermTerminal,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        self.fetch_feed(feed, false, terminal).await
+    }
+
+    /// Loads the feed into the focused feed pane, drawing the status bar as loading first.
+    async fn fetch_feed(
+        &mut self,
+        feed: MenuItem,
+        refresh: bool,
+        terminal: &mut CrosstermTerminal,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        self.loading = true;
+        terminal.draw(|frame| draw(frame, self))?;
+        self.load_feed(self.feed_focus(), feed, refresh).await;
+        self.loading = false;
+        Ok(())
+    }
+
+    /// Opens the panes of the session saved when hntui last quit, with their feeds where they
+    /// were left, and loads them.
+    pub async fn restore_session(
+        &mut self,
+        saved: SavedSession,
+        terminal: &mut CrosstermTerminal,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let scroll_past_list = self.config.scroll_past_list;
+        self.main = FeedPane::restored(saved.main, scroll_past_list);
+        match saved.split {
+            Some(PaneState::Feed(feed)) => {
+                self.split = Some(Pane::Feed(FeedPane::restored(feed, scroll_past_list)));
+            }
+            Some(PaneState::Thread(state)) => self.reopen_thread(state, terminal).await?,
+            None => {}
+        }
+        self.focus = if saved.split_focused && self.split.is_some() {
+            Focus::Split
+        } else {
+            Focus::Main
+        };
+
+        self.load_all().await;
+        Ok(())
+    }
+
+    /// Saves the panes and where their feeds were left to the session file, if the session
+    /// is restored on start.
+    pub fn save_session(&self) -> Result<(), std::io::Error> {
+        let session = match &self.session {
+            Some(session) => session,
+            None => return Ok(()),
+        };
+
+        session.write(&SavedSession {
+            main: self.main.state(),
+            split: self.split.as_ref().map(Pane::state),
+            split_focused: self.focus == Focus::Split,
+        })
+    }
+
+    /// Fetches the comment thread of the post and shows it in the second pane, replacing what
+    /// it showed before, and focuses it.
+    async fn open_thread(
+        &mut self,
+        id: u32,
+        terminal: &mut CrosstermTerminal,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        self.loading = true;
+        terminal.draw(|frame| draw(frame, self))?;
+        let thread = self.client.get_thread(id, None).await;
+        self.loading = false;
+
+        match thread {
+            Ok(thread) => {
+                self.history.open_thread(id, thread.post.descendants());
+                self.write_history();
+                self.split = Some(Pane::Thread(ThreadPane::new(thread)));
+                self.focus = Focus::Split;
+                self.update_list();
+            }
+            Err(err) => self
+                .notifications
+                .error(format!("Couldn't load the comments of {}: {}", id, err)),
+        }
+
+        Ok(())
+    }
+
+    /// Opens the thread again like it was left.
+    async fn reopen_thread(
+        &mut self,
+        state: ThreadState,
+        terminal: &mut CrosstermTerminal,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        self.open_thread(state.id, terminal).await?;
+        if let Some(Pane::Thread(thread)) = &mut self.split {
+            if thread.thread.post.id() == state.id {
+                thread.restore(state);
+            }
+        }
+        Ok(())
+    }
+
+    /// Generates the rows of every feed again, like after the history or the bookmarks changed.
+    pub fn update_list(&mut self) {
+        let (current_time, config, history, bookmarks) = (
+            self.current_time,
+            &self.config,
+            &self.history,
+            &self.bookmarks,
+        );
+        let items = &self.items;
+        let changes = |feed| feed_changes(feed, items);
+        self.main.update_list(
+            current_time,
+            config,
+            history,
+            bookmarks,
+            changes(self.main.feed),
+        );
+        if let Some(Pane::Feed(feed)) = &mut self.split {
+            feed.update_list(current_time, config, history, bookmarks, changes(feed.feed));
+        }
+    }
+
+    /// Returns the selected post of the focused feed or thread.
+    pub fn selected_post(&self) -> Option<&Post> {
+        match self.thread() {
+            Some(thread) => thread.selected_post(),
+            None => self.feed().selected_post(),
+        }
+    }
+
+    fn write_bookmarks(&mut self) {
+        if let Err(err) = self.bookmarks.write() {
+            self.notifications
+                .error(format!("Couldn't save the bookmarks: {}", err));
+        }
+    }
+
+    fn write_history(&mut self) {
+        if let Err(err) = self.history.write() {
+            self.notifications
+                .error(format!("Couldn't save the history: {}", err));
+        }
+    }
+
+    /// Opens the url in the browser, or shows why it couldn't be opened. Returns true if it
+    /// was opened.
+    fn open_url(
+        &mut self,
+        url: &str,
+        terminal: &mut CrosstermTerminal,
+    ) -> Result<bool, Box<dyn std::error::Error>> {
+        match webbrowser::open(url) {
+            Ok(_) => {
+                // Browsers running in the terminal leave their output behind.
+                terminal.clear()?;
+                Ok(true)
+            }
+            Err(err) => {
+                self.notifications
+                    .error(format!("Couldn't open {}: {}", url, err));
+                Ok(false)
+            }
+        }
+    }
+
+    /// Writes the visible posts of the active feed, or the thread of the post with the given
+    /// id, to the path. The format is guessed from the extension of the path.
+    async fn export(
+        &self,
+        path: &str,
+        thread: Option<u32>,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let path = std::path::Path::new(path);
+        let format = Format::from_path(path)
+            .ok_or_else(|| format!("Can't guess the export format of {}", path.display()))?;
+
+        let exported = match thread {
+            Some(id) => export::export_thread(&self.client.get_thread(id, None).await?, format)?,
+            None => {
+                let feed = self.feed();
+                export::export_feed(&feed.feed.to_string(), &feed.posts, format)?
+            }
+        };
+
+        Ok(std::fs::write(path, exported)?)
+    }
+
+    /// Handles a key press while a prompt is open. Returns the command entered in the command
+    /// palette, if it was confirmed.
+    pub async fn handle_prompt_key(&mut self, code: KeyCode) -> Option<Command> {
+        match code {
+            KeyCode::Esc => self.prompt = None,
+            KeyCode::Enter => {
+                if let Some(prompt) = self.prompt.take() {
+                    match prompt.kind {
+                        PromptKind::Tags(id) => {
+                            if let Some(bookmark) = self.bookmarks.get_mut(id) {
+                                bookmark.tags = prompt
+                                    .input
+                                    .split(',')
+                                    .map(str::trim)
+                                    .filter(|tag| !tag.is_empty())
+                                    .map(String::from)
+                                    .collect();
+                            }
+                        }
+                        PromptKind::Note(id) => {
+                            if let Some(bookmark) = self.bookmarks.get_mut(id) {
+                                bookmark.note = prompt.input.trim().to_string();
+                            }
+                        }
+                        PromptKind::Export | PromptKind::ExportThread(_) => {
+                            let thread = match prompt.kind {
+                                PromptKind::ExportThread(id) => Some(id),
+                                _ => None,
+                            };
+                            let path = prompt.input.trim();
+                            match self.export(path, thread).await {
+                                Ok(()) => self.notifications.info(format!("Exported to {}", path)),
+                                Err(err) => self
+                                    .notifications
+                                    .error(format!("Couldn't export to {}: {}", path, err)),
+                            }
+                            return None;
+                        }
+                        PromptKind::Command => {
+                            return match actions::parse_command(&prompt.input) {
+                                Ok(command) => Some(command),
+                                Err(err) => {
+                                    self.notifications.warning(err);
+                                    None
+                                }
+                            };
+                        }
+                    }
+
+                    self.write_bookmarks();
+                    self.update_list();
+                }
+            }
+            code => {
+                if let Some(prompt) = self.prompt.as_mut() {
+                    prompt.handle_key(code);
+                }
+            }
+        }
+
+        None
+    }
+
+    /// Runs a command from the command palette. Returns false if the app should quit.
+    pub async fn run_command(
+        &mut self,
+        command: Command,
+        terminal: &mut CrosstermTerminal,
+    ) -> Result<bool, Box<dyn std::error::Error>> {
+        match command {
+            Command::Run(action, None) => return self.perform(action, None, terminal).await,
+            Command::Run(action, Some(rank)) => {
+                if self.select_rank(rank) {
+                    return self.perform(action, None, terminal).await;
+                }
+            }
+            Command::Jump(rank) => {
+                self.select_rank(rank);
+            }
+            Command::Tab(tab) => self.switch_feed(tab, terminal).await?,
+            Command::User(user) => {
+                self.open_url(
+                    &format!("https://news.ycombinator.com/user?id={}", user),
+                    terminal,
+                )?;
+            }
+        }
+
+        Ok(true)
+    }
+
+    /// Focuses the clicked pane, selects the clicked post and opens its article on a double
+    /// click, switches to the clicked tab and moves the selection with the scroll wheel.
+    /// Returns false if the app should quit.
+    pub async fn handle_mouse(
+        &mut self,
+        event: MouseEvent,
+        terminal: &mut CrosstermTerminal,
+    ) -> Result<bool, Box<dyn std::error::Error>> {
+        let inside = |area: Rect| {
+            event.column >= area.left()
+                && event.column < area.right()
+                && event.row >= area.top()
+                && event.row < area.bottom()
+        };
+
+        match event.kind {
+            MouseEventKind::ScrollDown => self.move_by(1),
+            MouseEventKind::ScrollUp => self.move_by(-1),
+            MouseEventKind::Down(MouseButton::Left) if inside(self.tabs_area) => {
+                // The tabs are drawn on the row below the top border.
+                let titles: Vec<String> = MenuItem::ALL.iter().map(MenuItem::to_string).collect();
+                let area = Rect {
+                    x: self.tabs_area.x + 1,
+                    width: self.tabs_area.width.saturating_sub(2),
+                    ..self.tabs_area
+                };
+                if event.row == self.tabs_area.y + 1 {
+                    if let Some(index) = ui::tab_at(&titles, area, event.column) {
+                        self.switch_feed(MenuItem::ALL[index], terminal).await?;
+                    }
+                }
+            }
+            MouseEventKind::Down(MouseButton::Left) => {
+                if let Some(Pane::Thread(thread)) = &mut self.split {
+                    if inside(thread.area) {
+                        // Selects the post of the line clicked, below the top border.
+                        if let Some(row) = event.row.checked_sub(thread.area.y + 1) {
+                            if row < thread.height {
+                                let line = thread.scroll as usize + row as usize;
+                                if line < thread.lines.len() {
+                                    thread.select(thread.post_at(line));
+                                }
+                            }
+                        }
+                        self.focus = Focus::Split;
+                        return Ok(true);
+                    }
+                }
+
+                let pane = match self
+                    .feed_panes()
+                    .into_iter()
+                    .find(|pane| inside(self.feed_pane(*pane).list_area))
+                {
+                    Some(pane) => pane,
+                    None => return Ok(true),
+                };
+                self.focus = pane;
+
+                let feed = self.feed_mut();
+                let row = (event.row - feed.list_area.y) as usize;
+                if let Some(index) = feed.stateful_list.item_at(row) {
+                    let post = feed.stateful_list.post_at(index);
+                    feed.stateful_list.select_post(post);
+
+                    let double_click = matches!(
+                        self.last_click,
+                        Some((time, clicked_pane, clicked))
+                            if clicked_pane == pane && clicked == post && time.elapsed() < DOUBLE_CLICK
+                    );
+                    if double_click {
+                        self.last_click = None;
+                        return self.perform(Action::OpenArticle, None, terminal).await;
+                    }
+                    self.last_click = Some((Instant::now(), pane, post));
+                }
+            }
+            _ => {}
+        }
+
+        Ok(true)
+    }
+
+    /// Moves the selection of the focused feed by the number of posts, or scrolls the focused
+    /// thread by the number of lines.
+    fn move_by(&mut self, count: isize) {
+        match self.thread_mut() {
+            Some(thread) => thread.scroll_by(count),
+            None => self.feed_mut().stateful_list.move_by(count),
+        }
+    }
+
+    /// Scrolls the overlay with the movement keys and closes it with any other key.
+    pub fn handle_overlay_key(&mut self, key: event::KeyEvent) {
+        let page = (self.feed().list_height / 2).max(1);
+        self.overlay = match (self.overlay, self.config.action(&[keys::normalize(key)])) {
+            (Some((overlay, scroll)), Some(action)) => match action {
+                Action::Down => Some((overlay, scroll.saturating_add(1))),
+                Action::Up => Some((overlay, scroll.saturating_sub(1))),
+                Action::HalfPageDown | Action::PageDown => {
+                    Some((overlay, scroll.saturating_add(page)))
+                }
+                Action::HalfPageUp | Action::PageUp => Some((overlay, scroll.saturating_sub(page))),
+                _ => None,
+            },
+            _ => None,
+        };
+    }
+
+    /// Selects the post with the rank, or shows an error if it isn't in the list.
+    fn select_rank(&mut self, rank: usize) -> bool {
+        match self
+            .feed()
+            .posts
+            .iter()
+            .position(|(post_rank, _)| *post_rank == rank)
+        {
+            Some(index) => {
+                self.feed_mut().stateful_list.select_post(index);
+                true
+            }
+            None => {
+                self.notifications
+                    .warning(format!("There is no post ranked {}", rank));
+                false
+            }
+        }
+    }
+
+    /// Performs the action. The count typed before the keys, if any, repeats the movement
+    /// actions or selects a rank for `first` and `last`. Returns false if the app should quit.
+    pub async fn perform(
+        &mut self,
+        action: Action,
+        count: Option<usize>,
+        terminal: &mut CrosstermTerminal,
+    ) -> Result<bool, Box<dyn std::error::Error>> {
+        tracing::debug!(action = action.name(), count = ?count, "action");
+        match action {
+            Action::ViewComments => {
+                if let Some((id, descendants)) = self
+                    .selected_post()
+                    .map(|post| (post.id(), post.descendants()))
+                {
+                    let url = format!("https://news.ycombinator.com/item?id={}", id);
+                    if self.open_url(&url, terminal)? {
+                        self.history.open_thread(id, descendants);
+                        self.write_history();
+                        self.update_list();
+                    }
+                }
+            }
+            Action::OpenArticle => {
+                if let Some(post) = self.selected_post() {
+                    let id = post.id();
+                    // Posts without an article, like Ask HN posts, open their comments.
+                    let url = match post.url() {
+                        "" => format!("https://news.ycombinator.com/item?id={}", id),
+                        url => url.to_string(),
+                    };
+                    if self.open_url(&url, terminal)? {
+                        self.history.open_article(id);
+                        self.write_history();
+                        self.update_list();
+                    }
+                }
+            }
+            Action::Quit => return Ok(false),
+            Action::PreviousTab | Action::NextTab => {
+                let mut feed = self.feed().feed;
+                if action == Action::PreviousTab {
+                    feed.scroll_back();
+                } else {
+                    feed.scroll();
+                }
+                self.switch_feed(feed, terminal).await?;
+            }
+            Action::Up | Action::Down if self.thread().is_some() => {
+                if let Some(thread) = self.thread_mut() {
+                    let posts = count.unwrap_or(1) as isize;
+                    thread.move_selection(if action == Action::Up { -posts } else { posts });
+                }
+            }
+            Action::Up => match count {
+                Some(count) => self.move_by(-(count as isize)),
+                None => self.feed_mut().stateful_list.previous(),
+            },
+            Action::Down => match count {
+                Some(count) => self.move_by(count as isize),
+                None => self.feed_mut().stateful_list.next(),
+            },
+            Action::HalfPageDown | Action::HalfPageUp | Action::PageDown | Action::PageUp => {
+                let page = match self.thread_mut() {
+                    Some(thread) => thread.height as isize,
+                    None => {
+                        let feed = self.feed();
+                        feed.stateful_list.page_size(feed.list_height as usize) as isize
+                    }
+                };
+                let distance = match action {
+                    Action::HalfPageDown => (page / 2).max(1),
+                    Action::HalfPageUp => -(page / 2).max(1),
+                    Action::PageDown => page,
+                    _ => -page,
+                };
+                self.move_by(distance * count.unwrap_or(1) as isize);
+            }
+            Action::First | Action::Last if self.thread().is_some() => {
+                if let Some(thread) = self.thread_mut() {
+                    thread.select(if action == Action::First {
+                        0
+                    } else {
+                        usize::MAX
+                    });
+                }
+            }
+            Action::First | Action::Last => match count {
+                Some(rank) => {
+                    self.select_rank(rank);
+                }
+                None if action == Action::First => self.feed_mut().stateful_list.first(),
+                None => self.feed_mut().stateful_list.last(),
+            },
+            Action::Refresh => match self.thread_mut() {
+                Some(thread) => {
+                    let state = thread.state();
+                    self.reopen_thread(state, terminal).await?;
+                }
+                None => self.fetch(true, terminal).await?,
+            },
+            Action::ToggleHidden => {
+                self.show_hidden = !self.show_hidden;
+                self.load_all().await;
+            }
+            Action::Sort => {
+                self.sort_mode.cycle();
+                self.load_all().await;
+            }
+            Action::Bookmark => {
+                if let Some(post) = self.selected_post().cloned() {
+                    self.bookmarks.toggle(&post);
+                    self.write_bookmarks();
+                    let saved_shown = self
+                        .feed_panes()
+                        .into_iter()
+                        .any(|pane| self.feed_pane(pane).feed == MenuItem::Saved);
+                    if saved_shown {
+                        self.load_all().await;
+                    } else {
+                        self.update_list();
+                    }
+                }
+            }
+            Action::Tag | Action::Note => {
+                if let Some(bookmark) = self
+                    .selected_post()
+                    .and_then(|post| self.bookmarks.get(post.id()))
+                {
+                    let id = bookmark.post.id();
+                    self.prompt = Some(if action == Action::Tag {
+                        Prompt::new(PromptKind::Tags(id), "Tags: ", bookmark.tags.join(", "))
+                    } else {
+                        Prompt::new(PromptKind::Note(id), "Note: ", bookmark.note.clone())
+                    });
+                }
+            }
+            Action::Export => {
+                let path = format!(
+                    "hn-{}-{}.md",
+                    self.feed().feed.to_string().to_lowercase(),
+                    self.current_time.format("%Y%m%d-%H%M")
+                );
+                self.prompt = Some(Prompt::new(PromptKind::Export, "Export to: ", path));
+            }
+            Action::ExportThread => {
+                if let Some(post) = self.selected_post() {
+                    let path = format!("hn-{}.md", post.id());
+                    self.prompt = Some(Prompt::new(
+                        PromptKind::ExportThread(post.id()),
+                        "Export thread to: ",
+                        path,
+                    ));
+                }
+            }
+            Action::ReloadConfig => self.reload_config().await,
+            Action::CommandPalette => {
+                self.prompt = Some(Prompt::new(PromptKind::Command, ":", String::new()))
+            }
+            Action::Help => self.overlay = Some((Overlay::Help, 0)),
+            Action::Log => {
+                // Opens scrolled to the end, as the scroll is limited to the length of the log.
+                self.overlay = Some((Overlay::Log, u16::MAX));
+            }
+            Action::Split => match self.split {
+                Some(_) => {
+                    self.split = None;
+                    self.focus = Focus::Main;
+                }
+                None => {
+                    // Shows the next feed, which can be changed with the tabs once focused.
+                    let mut feed = self.main.feed;
+                    feed.scroll();
+                    self.split = Some(Pane::Feed(FeedPane::new(
+                        feed,
+                        self.config.scroll_past_list,
+                    )));
+                    self.focus = Focus::Split;
+                    self.fetch(false, terminal).await?;
+                }
+            },
+            Action::Thread => {
+                if let Some(id) = self.feed().selected_post().map(Post::id) {
+                    self.open_thread(id, terminal).await?;
+                }
+            }
+            Action::DismissBanner => self.notifications.dismiss_banner(),
+            Action::Collapse => {
+                if let Some(thread) = self.thread_mut() {
+                    thread.toggle_collapsed();
+                }
+            }
+            Action::SwitchPane => match (self.split.is_some(), self.focus) {
+                (false, _) => self
+                    .notifications
+                    .warning("There is only one pane, split the view first"),
+                (true, Focus::Main) => self.focus = Focus::Split,
+                (true, Focus::Split) => self.focus = Focus::Main,
+            },
+        }
+
+        Ok(true)
+    }
+}
+
+/// Summarizes the problems found in the config, if there are any.
+fn config_warning(config: &Config) -> Option<String> {
+    let first = config.errors.first()?;
+    let more = config.errors.len() - 1;
+    Some(format!(
+        "Config: {}{}, run `hntui config check` for details",
+        first,
+        if more > 0 {
+            format!(" (and {} more)", more)
+        } else {
+            String::new()
+        }
+    ))
+}
@@ -0,0 +1,89 @@
+use crate::files;
+use api::Post;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use time::OffsetDateTime;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Bookmark {
+    /// A snapshot of the post at the time it was saved.
+    pub post: Post,
+    #[serde(with = "time::serde::timestamp")]
+    pub saved_at: OffsetDateTime,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    #[serde(default)]
+    pub note: String,
+}
+
+#[derive(Debug, Default)]
+pub struct Bookmarks {
+    path: PathBuf,
+    /// The saved posts, most recently saved first.
+    bookmarks: Vec<Bookmark>,
+    /// Why the bookmarks file couldn't be read, if it couldn't.
+    pub read_error: Option<String>,
+}
+
+impl Bookmarks {
+    pub fn new() -> Result<Self, std::io::Error> {
+        let path = files::data_file("bookmarks.json")?;
+        let (bookmarks, read_error) = files::load_json(&path, "starting with no bookmarks")?;
+
+        Ok(Bookmarks {
+            path,
+            bookmarks,
+            read_error,
+        })
+    }
+
+    pub fn write(&self) -> Result<(), std::io::Error> {
+        std::fs::write(&self.path, serde_json::to_string_pretty(&self.bookmarks)?)
+    }
+
+    /// Saves the post, or removes it if it was already saved.
+    /// Returns true if the post is saved afterwards.
+    pub fn toggle(&mut self, post: &Post) -> bool {
+        let id = post.id();
+        if self.contains(id) {
+            self.bookmarks.retain(|bookmark| bookmark.post.id() != id);
+            false
+        } else {
+            self.bookmarks.insert(
+                0,
+                Bookmark {
+                    post: post.clone(),
+                    saved_at: OffsetDateTime::now_utc(),
+                    tags: Vec::new(),
+                    note: String::new(),
+                },
+            );
+            true
+        }
+    }
+
+    pub fn contains(&self, id: u32) -> bool {
+        self.get(id).is_some()
+    }
+
+    pub fn get(&self, id: u32) -> Option<&Bookmark> {
+        self.bookmarks
+            .iter()
+            .find(|bookmark| bookmark.post.id() == id)
+    }
+
+    pub fn get_mut(&mut self, id: u32) -> Option<&mut Bookmark> {
+        self.bookmarks
+            .iter_mut()
+            .find(|bookmark| bookmark.post.id() == id)
+    }
+
+    /// Returns the saved posts ranked in the order they were saved, most recent first.
+    pub fn get_vec(&self) -> Vec<(usize, Post)> {
+        self.bookmarks
+            .iter()
+            .enumerate()
+            .map(|(pos, bookmark)| (pos + 1, bookmark.post.clone()))
+            .collect()
+    }
+}
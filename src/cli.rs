@@ -0,0 +1,274 @@
+use crate::{
+    bookmarks::Bookmarks,
+    config::Config,
+    export::{self, Format},
+    items::construct_items,
+};
+use api::Post;
+use std::{
+    io::{self, Write},
+    path::PathBuf,
+};
+
+pub const USAGE: &str = "Usage:
+    hntui                   Start the terminal interface
+    hntui help              Print this message
+    hntui <FEED>            Print a feed: top, new, ask, show, jobs or saved
+    hntui item <ID>         Print an item
+    hntui user <NAME>       Print a user
+    hntui comments <ID>     Print an item with its comment thread
+    hntui export <FEED|ID>  Export a feed or the comment thread of an item
+    hntui config check      Validate the config and list every problem
+
+Options:
+    --config <PATH>         Use the config file at PATH
+    --max-items <N>         Override general.max_items
+    --view <VIEW>           Override general.default_view
+    --no-scroll-past-list   Override general.scroll_past_list with false
+    --api-url <URL>         Override general.api_url
+    --log-level <LEVEL>     Override general.log_level: off, error, warn, info, debug or trace
+    --set <SECTION.KEY=VALUE>
+                            Override any config value
+    --print-config          Print the effective config and exit
+    --format <FORMAT>       json, csv, md or text. Export guesses it from the output path
+    --output <PATH>         Write to a file instead of stdout
+    --limit <N>             The number of posts to print from a feed
+    --depth <N>             The number of reply levels to print from a comment thread
+
+Config values can also be set with HNTUI_<SECTION>_<KEY> environment variables, like
+HNTUI_GENERAL_MAX_ITEMS or HNTUI_KEYBINDINGS_QUIT, and the API with HNTUI_API_URL.
+Values given on the command line take precedence over environment variables, which take
+precedence over the config file.
+
+Requests, feed loads and actions are logged to hntui.log in the data directory, which is
+rotated once it reaches 1 MiB.";
+
+const FEEDS: &[&str] = &["top", "new", "ask", "show", "jobs", "saved"];
+
+pub enum Command {
+    Help,
+    Feed(String),
+    Item(u32),
+    User(String),
+    Comments(u32),
+    Export(String),
+    CheckConfig,
+}
+
+#[derive(Default)]
+pub struct Options {
+    pub config: Option<PathBuf>,
+    /// Config values set on the command line, as `(section, key, value)`.
+    pub overrides: Vec<(String, String, String)>,
+    pub print_config: bool,
+    pub format: Option<Format>,
+    pub output: Option<PathBuf>,
+    pub limit: Option<u16>,
+    pub depth: Option<usize>,
+}
+
+/// Parses the command line arguments, without the program name.
+/// The command is `None` if no subcommand was given and the TUI should be started.
+pub fn parse(args: &[String]) -> Result<(Option<Command>, Options), String> {
+    let mut args = args.iter();
+    let mut positional = Vec::new();
+    let mut options = Options::default();
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "-h" | "--help" => return Ok((Some(Command::Help), options)),
+            "--config" => options.config = Some(PathBuf::from(value(arg, args.next())?)),
+            "--max-items" => {
+                let max_items: u16 = number(arg, args.next())?;
+                options.set("general", "max_items", max_items.to_string());
+            }
+            "--view" => options.set("general", "default_view", value(arg, args.next())?),
+            "--no-scroll-past-list" => options.set("general", "scroll_past_list", "false"),
+            "--api-url" => options.set("general", "api_url", value(arg, args.next())?),
+            "--log-level" => options.set("general", "log_level", value(arg, args.next())?),
+            "--set" => {
+                let setting = value(arg, args.next())?;
+                let (key, value) = setting
+                    .split_once('=')
+                    .ok_or_else(|| format!("{} should look like section.key=value", setting))?;
+                let (section, key) = key
+                    .split_once('.')
+                    .ok_or_else(|| format!("{} should look like section.key=value", setting))?;
+                options.set(section.trim(), key.trim(), value.trim());
+            }
+            "--print-config" => options.print_config = true,
+            "--format" => options.format = Some(value(arg, args.next())?.parse()?),
+            "--output" => options.output = Some(PathBuf::from(value(arg, args.next())?)),
+            "--limit" => options.limit = Some(number(arg, args.next())?),
+            "--depth" => options.depth = Some(number(arg, args.next())?),
+            flag if flag.starts_with("--") => return Err(format!("Unknown option {}", flag)),
+            _ => positional.push(arg.as_str()),
+        }
+    }
+
+    let command = match positional.as_slice() {
+        [] => return Ok((None, options)),
+        ["help"] => Command::Help,
+        [feed] if FEEDS.contains(feed) => Command::Feed(feed.to_string()),
+        ["item", id] => Command::Item(id_argument(id)?),
+        ["user", name] => Command::User(name.to_string()),
+        ["comments", id] => Command::Comments(id_argument(id)?),
+        ["export", target] => Command::Export(target.to_string()),
+        ["config", "check"] => Command::CheckConfig,
+        ["config", ..] => return Err(String::from("config expects the check subcommand")),
+        [command, ..] if ["item", "user", "comments", "export"].contains(command) => {
+            return Err(format!("{} expects exactly one argument", command))
+        }
+        [command, ..] => return Err(format!("Unknown command {}", command)),
+    };
+
+    Ok((Some(command), options))
+}
+
+impl Options {
+    fn set<S: Into<String>>(&mut self, section: &str, key: &str, value: S) {
+        self.overrides
+            .push((section.to_string(), key.to_string(), value.into()));
+    }
+}
+
+fn value<'a>(flag: &str, value: Option<&'a String>) -> Result<&'a str, String> {
+    value
+        .map(String::as_str)
+        .ok_or_else(|| format!("{} expects a value", flag))
+}
+
+fn number<T: std::str::FromStr>(flag: &str, value: Option<&String>) -> Result<T, String> {
+    let value = self::value(flag, value)?;
+    value
+        .parse()
+        .map_err(|_| format!("{} is not a valid {} value", value, flag))
+}
+
+fn id_argument(id: &str) -> Result<u32, String> {
+    id.parse()
+        .map_err(|_| format!("{} is not a valid item id", id))
+}
+
+/// Fetches the first `limit` posts of a feed, without the posts muted by the filters.
+async fn get_feed(
+    feed: &str,
+    limit: u16,
+    client: api::Client,
+    config: &Config,
+) -> Result<Vec<(usize, Post)>, Box<dyn std::error::Error>> {
+    let options = format!("orderBy=\"$key\"&limitToFirst={}", limit);
+    let stories = match feed {
+        "top" => client.get_top_stories(&options).await?,
+        "new" => client.get_new_stories(&options).await?,
+        "ask" => client.get_ask_stories(&options).await?,
+        "show" => client.get_show_stories(&options).await?,
+        "jobs" => client.get_job_stories(&options).await?,
+        _ => {
+            let mut saved = Bookmarks::new()?.get_vec();
+            saved.truncate(limit as usize);
+            return Ok(saved);
+        }
+    };
+
+    Ok(config
+        .filters
+        .apply(construct_items(stories, client).await?, false)
+        .0)
+}
+
+pub async fn run(
+    command: Command,
+    options: Options,
+    client: api::Client,
+    config: &Config,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let limit = options.limit.unwrap_or(config.max_items);
+    let format = options.format.unwrap_or(Format::Text);
+
+    let output = match command {
+        Command::Help => format!("{}\n", USAGE),
+        Command::CheckConfig => {
+            if config.errors.is_empty() {
+                format!("{} is valid\n", config.path().display())
+            } else {
+                for error in &config.errors {
+                    eprintln!("{}", error);
+                }
+                eprintln!(
+                    "{} {} found in {}",
+                    config.errors.len(),
+                    if config.errors.len() == 1 {
+                        "problem"
+                    } else {
+                        "problems"
+                    },
+                    config.path().display()
+                );
+                std::process::exit(1);
+            }
+        }
+        Command::Feed(feed) => {
+            let items = get_feed(&feed, limit, client, config).await?;
+            export::export_feed(&feed, &items, format)?
+        }
+        Command::Item(id) if format == Format::Json => {
+            serde_json::to_string_pretty(&client.get_item_by_id(id, "").await?)?
+        }
+        Command::Item(id) => {
+            let thread = client.get_thread(id, Some(0)).await?;
+            export::export_thread(&thread, format)?
+        }
+        Command::User(name) => {
+            export::export_user(&client.get_user_by_id(&name, "").await?, format)?
+        }
+        Command::Comments(id) => {
+            let thread = client.get_thread(id, options.depth).await?;
+            export::export_thread(&thread, format)?
+        }
+        Command::Export(target) => {
+            let format = options
+                .format
+                .or_else(|| options.output.as_deref().and_then(Format::from_path))
+                .unwrap_or(Format::Json);
+
+            if FEEDS.contains(&target.as_str()) {
+                let items = get_feed(&target, limit, client, config).await?;
+                export::export_feed(&target, &items, format)?
+            } else {
+                let thread = client
+                    .get_thread(id_argument(&target)?, options.depth)
+                    .await?;
+                export::export_thread(&thread, format)?
+            }
+        }
+    };
+
+    match options.output {
+        Some(path) => std::fs::write(path, output)?,
+        None => write_stdout(&output)?,
+    }
+
+    Ok(())
+}
+
+/// Writes the output to stdout, ending it with a newline. A reader that stops early, like
+/// `head`, closes the pipe, which isn't an error.
+fn write_stdout(output: &str) -> Result<(), io::Error> {
+    let mut stdout = io::stdout().lock();
+    let result = stdout
+        .write_all(output.as_bytes())
+        .and_then(|_| {
+            if output.ends_with('\n') {
+                Ok(())
+            } else {
+                stdout.write_all(b"\n")
+            }
+        })
+        .and_then(|_| stdout.flush());
+
+    match result {
+        Err(err) if err.kind() == io::ErrorKind::BrokenPipe => Ok(()),
+        result => result,
+    }
+}
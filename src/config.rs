@@ -1,94 +1,198 @@
-use crate::ui::MenuItem;
-use crossterm::event::KeyCode;
-use directories::ProjectDirs;
-use std::{collections::HashSet, path::PathBuf};
+use crate::{
+    actions::Action,
+    files,
+    filters::Filters,
+    highlight::{HighlightRule, Highlights},
+    keys::{self, Keybinding},
+    template::{self, Template},
+    theme::Theme,
+    ui::{format_style, LayoutMode, MenuItem, SplitLayout},
+};
+use regex::Regex;
+use std::{collections::HashMap, fmt, path::PathBuf, time::SystemTime};
 use tini::Ini;
+use tracing::level_filters::LevelFilter;
+
+/// The sections that can be set with `HNTUI_<SECTION>_<KEY>` environment variables.
+const SECTIONS: &[&str] = &[
+    "keybindings",
+    "general",
+    "filters",
+    "highlight",
+    "theme",
+    "rows",
+];
+
+/// Where the value of a config key was set.
+#[derive(Clone, Debug)]
+pub enum Origin {
+    Default,
+    /// The config file, at the given line if it could be found.
+    File(Option<usize>),
+    Environment(String),
+    CommandLine,
+}
+
+/// A problem with a single config value, or with the config file as a whole if `key` is `None`.
+#[derive(Debug)]
+pub struct ConfigError {
+    pub section: String,
+    pub key: Option<String>,
+    pub origin: Origin,
+    pub message: String,
+}
+
+impl fmt::Display for Origin {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Origin::Default => write!(f, "default value"),
+            Origin::File(Some(line)) => write!(f, "line {}", line),
+            Origin::File(None) => write!(f, "config file"),
+            Origin::Environment(var) => write!(f, "{}", var),
+            Origin::CommandLine => write!(f, "command line"),
+        }
+    }
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.origin)?;
+
+        match &self.key {
+            Some(key) => write!(f, ": [{}] {}: {}", self.section, key, self.message),
+            None if !self.section.is_empty() => write!(f, ": [{}]: {}", self.section, self.message),
+            None => write!(f, ": {}", self.message),
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {}
 
 #[derive(Debug)]
 pub struct Config {
     ini: Ini,
     path: PathBuf,
-    pub view_comments: HashSet<KeyCode>,
-    pub quit: HashSet<KeyCode>,
-    pub down: HashSet<KeyCode>,
-    pub up: HashSet<KeyCode>,
-    pub left: HashSet<KeyCode>,
-    pub right: HashSet<KeyCode>,
-    pub open_article: HashSet<KeyCode>,
-    pub refresh: HashSet<KeyCode>,
+    origins: HashMap<(String, String), Origin>,
+    /// The command line overrides the config was loaded with, to apply them again on reload.
+    overrides: Vec<(String, String, String)>,
+    /// The modification time of the config file when it was loaded.
+    modified: Option<SystemTime>,
+    /// The problems found while loading the config. Invalid values are replaced by their default.
+    pub errors: Vec<ConfigError>,
+    /// The keys bound to each action. Actions without keys can still be run from the command
+    /// palette.
+    pub keybindings: HashMap<Action, Keybinding>,
     pub max_items: u16,
     pub default_view: MenuItem,
     pub scroll_past_list: bool,
+    pub layout: LayoutMode,
+    /// How the panes are arranged when the view is split.
+    pub split_layout: SplitLayout,
+    /// Whether the TUI captures the mouse, which stops the terminal from selecting text.
+    pub mouse: bool,
+    pub api_url: String,
+    /// Whether the panes, feeds and selections are saved on quit and opened again on start.
+    pub restore_session: bool,
+    /// The least severe events written to the log file, read once at startup.
+    pub log_level: LevelFilter,
+    pub filters: Filters,
+    pub highlights: Highlights,
+    pub theme: Theme,
+    /// The templates of the lines every post takes up in the list, from the `[rows]` section.
+    pub rows: Vec<Template>,
+    /// The template of the single line of every post in the compact layout.
+    pub compact_row: Template,
 }
 
 impl std::default::Default for Config {
     fn default() -> Self {
-        let mut view_comments = HashSet::new();
-        view_comments.insert(KeyCode::Char('c'));
-
-        let mut quit = HashSet::new();
-        quit.insert(KeyCode::Char('q'));
-        quit.insert(KeyCode::Esc);
-
-        let mut down = HashSet::new();
-        down.insert(KeyCode::Char('j'));
-        down.insert(KeyCode::Down);
-
-        let mut up = HashSet::new();
-        up.insert(KeyCode::Char('k'));
-        up.insert(KeyCode::Up);
-
-        let mut left = HashSet::new();
-        left.insert(KeyCode::Char('h'));
-        left.insert(KeyCode::Left);
-
-        let mut right = HashSet::new();
-        right.insert(KeyCode::Char('l'));
-        right.insert(KeyCode::Right);
-
-        let mut open_article = HashSet::new();
-        open_article.insert(KeyCode::Enter);
-
-        let mut refresh = HashSet::new();
-        refresh.insert(KeyCode::Char('r'));
-
-        Config {
-            ini: Ini::new()
-                .section("keybindings")
-                .item_vec("view_comments", &["c"])
-                .item_vec("quit", &["q", "esc"])
-                .item_vec("down", &["j", "arrow_down"])
-                .item_vec("up", &["k", "arrow_up"])
-                .item_vec("left", &["h", "arrow_left"])
-                .item_vec("right", &["l", "arrow_right"])
-                .item_vec("open_article", &["enter"])
-                .item_vec("refresh", &["r"])
+        let mut config = Config {
+            ini: Action::ALL
+                .iter()
+                .fold(Ini::new().section("keybindings"), |ini, action| {
+                    ini.item(action.name(), action.default_keys())
+                })
                 .section("general")
                 .item("max_items", 30)
                 .item("default_view", "top")
-                .item("scroll_past_list", "true"),
-            path: Self::config_path(),
-            view_comments,
-            quit,
-            down,
-            up,
-            left,
-            right,
-            open_article,
-            refresh,
+                .item("scroll_past_list", "true")
+                .item("mouse", "true")
+                .item("layout", "auto")
+                .item("split_layout", "auto")
+                .item("api_url", api::BASE_URL)
+                .item("restore_session", "false")
+                .item("log_level", "info")
+                .section("filters")
+                .item("domains", "")
+                .item("authors", "")
+                .item("titles", "")
+                .section("highlight")
+                .section("theme")
+                .item("name", "default")
+                .section("rows")
+                .item("line1", template::DEFAULT_LINES[0])
+                .item("line2", template::DEFAULT_LINES[1])
+                .item("line3", template::DEFAULT_LINES[2])
+                .item("compact", template::DEFAULT_COMPACT),
+            path: PathBuf::new(),
+            origins: HashMap::new(),
+            overrides: Vec::new(),
+            modified: None,
+            errors: Vec::new(),
+            keybindings: HashMap::new(),
             max_items: 30,
             default_view: MenuItem::Top,
             scroll_past_list: true,
-        }
+            mouse: true,
+            layout: LayoutMode::Auto,
+            split_layout: SplitLayout::Auto,
+            api_url: api::BASE_URL.to_string(),
+            restore_session: false,
+            log_level: LevelFilter::INFO,
+            filters: Filters::default(),
+            highlights: Highlights::default(),
+            theme: Theme::default(),
+            rows: Vec::new(),
+            compact_row: Template::default(),
+        };
+
+        // The keybindings are only defined in the ini above.
+        config.parse();
+        config
     }
 }
 
 impl Config {
-    pub fn new() -> Result<Self, std::io::Error> {
-        let mut config = Self::default();
+    /// Loads the config file at `path`, or at the default location if no path is given, and
+    /// applies the `HNTUI_*` environment variables and then the `(section, key, value)`
+    /// overrides on top of it. Keys missing from all of them keep their default value.
+    /// Problems with the values are collected in `errors` instead of failing the whole load.
+    pub fn load(
+        path: Option<PathBuf>,
+        overrides: &[(String, String, String)],
+    ) -> Result<Self, std::io::Error> {
+        Self::load_with(path, overrides, std::env::vars())
+    }
+
+    /// Loads the config like `load`, with the environment variables given instead of read.
+    fn load_with<I>(
+        path: Option<PathBuf>,
+        overrides: &[(String, String, String)],
+        vars: I,
+    ) -> Result<Self, std::io::Error>
+    where
+        I: IntoIterator<Item = (String, String)>,
+    {
+        let mut config = Config {
+            path: match path {
+                Some(path) => path,
+                None => Self::config_path()?,
+            },
+            ..Self::default()
+        };
 
         if let Some(parent) = config.path.parent() {
-            if !parent.exists() {
+            if !parent.as_os_str().is_empty() && !parent.exists() {
                 std::fs::create_dir_all(parent)?;
             }
         }
@@ -96,87 +200,522 @@ impl Config {
         if !config.path.exists() {
             config.write()?;
         } else if let Err(err) = config.read() {
-            eprintln!("{:#?}, {:#?}", err, config.path);
+            let line = match &err {
+                tini::Error::Parse(tini::ParseError::IncorrectSection(line))
+                | tini::Error::Parse(tini::ParseError::IncorrectSyntax(line))
+                | tini::Error::Parse(tini::ParseError::EmptyKey(line)) => Some(*line),
+                tini::Error::Io(_) => None,
+            };
+            config.errors.push(ConfigError {
+                section: String::new(),
+                key: None,
+                origin: Origin::File(line),
+                message: format!("{}, using the default config", err),
+            });
         }
 
+        config.apply_overrides(overrides, vars);
         Ok(config)
     }
 
-    pub fn read(&mut self) -> Result<(), tini::Error> {
-        self.ini = Ini::from_file(&self.path)?;
-        self.default_view = match self.ini.get::<String>("general", "default_view") {
-            Some(value) => match value.to_lowercase().as_str() {
-                "top" => MenuItem::Top,
-                "new" => MenuItem::New,
-                _ => {
-                    eprintln!("{} is not a valid default_view value", value);
-                    MenuItem::Top
+    /// Loads the config again from the same path and with the same overrides. Unlike when
+    /// hntui starts, a missing file isn't written with the defaults, as it was deleted, and a
+    /// file that can't be parsed is an error instead of giving the default config, so the
+    /// current config can be kept while the file is being edited.
+    pub fn reload(&self) -> Result<Self, std::io::Error> {
+        if !self.path.exists() {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::NotFound,
+                "the file doesn't exist anymore",
+            ));
+        }
+
+        let mut config = Config {
+            path: self.path.clone(),
+            ..Self::default()
+        };
+        config.read().map_err(|err| {
+            std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                format!("{}, keeping the current config", err),
+            )
+        })?;
+        config.apply_overrides(&self.overrides, std::env::vars());
+        Ok(config)
+    }
+
+    /// Applies the `HNTUI_*` variables of `vars` and then the command line overrides on top of
+    /// the values read from the file, and parses the result.
+    fn apply_overrides<I>(&mut self, overrides: &[(String, String, String)], vars: I)
+    where
+        I: IntoIterator<Item = (String, String)>,
+    {
+        for (section, key, value, var) in Self::env_overrides(vars) {
+            self.set(&section, &key, &value, Origin::Environment(var));
+        }
+        for (section, key, value) in overrides {
+            self.set(section, key, value, Origin::CommandLine);
+        }
+
+        self.overrides = overrides.to_vec();
+        self.modified = self.file_modified();
+        self.parse();
+    }
+
+    /// Takes the file as it is now as loaded, so `changed` doesn't report a change that
+    /// couldn't be loaded again until the file changes once more.
+    pub fn ignore_change(&mut self) {
+        self.modified = self.file_modified();
+    }
+
+    /// Returns true if the config file was modified since the config was loaded.
+    pub fn changed(&self) -> bool {
+        self.file_modified() != self.modified
+    }
+
+    fn file_modified(&self) -> Option<SystemTime> {
+        std::fs::metadata(&self.path)
+            .and_then(|metadata| metadata.modified())
+            .ok()
+    }
+
+    pub fn path(&self) -> &std::path::Path {
+        &self.path
+    }
+
+    fn read(&mut self) -> Result<(), tini::Error> {
+        let contents = std::fs::read_to_string(&self.path)?;
+        let file = Ini::from_string(contents.as_str())?;
+        for (section, section_iter) in file.iter() {
+            for (key, value) in section_iter {
+                let line = Self::find_line(&contents, section, key);
+                self.set(section, key, value, Origin::File(line));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Returns the 1-based line number of `key` in `section` of the config file contents.
+    fn find_line(contents: &str, section: &str, key: &str) -> Option<usize> {
+        let mut current_section = "";
+        for (index, line) in contents.lines().enumerate() {
+            let line = line.trim();
+            if let Some(name) = line
+                .strip_prefix('[')
+                .and_then(|line| line.strip_suffix(']'))
+            {
+                current_section = name.trim();
+            } else if current_section == section {
+                if let Some((name, _)) = line.split_once('=') {
+                    if name.trim() == key {
+                        return Some(index + 1);
+                    }
                 }
-            },
-            _ => MenuItem::Top,
+            }
+        }
+
+        None
+    }
+
+    /// Maps `HNTUI_<SECTION>_<KEY>` variables to `(section, key, value, variable)` overrides,
+    /// e.g. `HNTUI_GENERAL_MAX_ITEMS` to `general.max_items`. `HNTUI_API_URL` is a shorthand
+    /// for `HNTUI_GENERAL_API_URL`.
+    fn env_overrides<I>(vars: I) -> Vec<(String, String, String, String)>
+    where
+        I: IntoIterator<Item = (String, String)>,
+    {
+        let mut overrides = Vec::new();
+        let mut api_url = None;
+
+        for (var, value) in vars {
+            let name = match var.strip_prefix("HNTUI_") {
+                Some(name) => name.to_lowercase(),
+                None => continue,
+            };
+
+            if name == "api_url" {
+                api_url = Some((value, var));
+                continue;
+            }
+
+            for section in SECTIONS {
+                if let Some(key) = name
+                    .strip_prefix(section)
+                    .and_then(|key| key.strip_prefix('_'))
+                {
+                    overrides.push((section.to_string(), key.to_string(), value, var));
+                    break;
+                }
+            }
+        }
+
+        if let Some((api_url, var)) = api_url {
+            overrides.insert(
+                0,
+                ("general".to_string(), "api_url".to_string(), api_url, var),
+            );
+        }
+
+        overrides
+    }
+
+    /// Sets a value in the effective configuration. Call `parse` afterwards to apply it.
+    fn set(&mut self, section: &str, key: &str, value: &str, origin: Origin) {
+        // Keybindings set under an old action name replace the current name.
+        let key = match Action::from_name(key) {
+            Some(action) if section == "keybindings" => action.name(),
+            _ => key,
         };
 
+        self.ini = std::mem::take(&mut self.ini)
+            .section(section)
+            .item(key, value);
+        self.origins
+            .insert((section.to_string(), key.to_string()), origin);
+    }
+
+    /// Returns the effective configuration in the same format as the config file. It's written
+    /// from the parsed values, so invalid values show the default used in their place.
+    pub fn to_ini_string(&self) -> String {
+        let ini = Action::ALL
+            .iter()
+            .fold(Ini::new().section("keybindings"), |ini, action| {
+                let keys = self.keybinding(*action).map(ToString::to_string);
+                ini.item(action.name(), keys.unwrap_or_default())
+            })
+            .section("general")
+            .item("max_items", self.max_items)
+            .item("default_view", self.default_view.to_string().to_lowercase())
+            .item("scroll_past_list", self.scroll_past_list)
+            .item("mouse", self.mouse)
+            .item("layout", self.layout)
+            .item("split_layout", self.split_layout)
+            .item("api_url", &self.api_url)
+            .item("restore_session", self.restore_session)
+            .item("log_level", self.log_level.to_string().to_lowercase())
+            .section("filters")
+            .item("domains", join_list(&self.filters.domains))
+            .item("authors", join_list(&self.filters.authors))
+            .item(
+                "titles",
+                join_list(self.filters.titles.iter().map(Regex::as_str)),
+            );
+
+        let ini = self
+            .highlights
+            .rules
+            .iter()
+            .fold(ini.section("highlight"), |ini, (key, rule)| {
+                ini.item(key.as_str(), rule)
+            });
+        let ini = self
+            .theme
+            .elements()
+            .iter()
+            .fold(ini.section("theme"), |ini, (element, style)| {
+                ini.item(*element, format_style(*style))
+            });
+        let ini = (0..template::DEFAULT_LINES.len())
+            .fold(ini.section("rows"), |ini, line| {
+                let template = self.rows.get(line).map(ToString::to_string);
+                ini.item(format!("line{}", line + 1), template.unwrap_or_default())
+            })
+            .item("compact", &self.compact_row);
+
+        ini.to_string()
+    }
+
+    /// Applies the values of the effective configuration to the fields. Invalid values leave
+    /// the field at its default and are recorded in `errors`.
+    fn parse(&mut self) {
+        let mut errors: Vec<(String, Option<String>, String)> = Vec::new();
+        let mut lines: Vec<Template> = template::DEFAULT_LINES
+            .iter()
+            .map(|line| line.parse().unwrap_or_default())
+            .collect();
+
         for (name, section_iter) in self.ini.iter() {
             match name.as_str() {
                 "keybindings" => {
                     for (key, value) in section_iter {
-                        let shortcuts: Vec<&str> = value.split(", ").collect();
-                        match key.as_str() {
-                            "view_comments" => {
-                                self.view_comments = Self::parse_shortcuts(shortcuts)
+                        let action = match Action::from_name(key) {
+                            Some(action) => action,
+                            None => {
+                                errors.push((
+                                    name.clone(),
+                                    Some(key.clone()),
+                                    String::from("unknown keybinding"),
+                                ));
+                                continue;
+                            }
+                        };
+
+                        match Keybinding::parse(value) {
+                            Ok(keybinding) => {
+                                self.keybindings.insert(action, keybinding);
                             }
-                            "quit" => self.quit = Self::parse_shortcuts(shortcuts),
-                            "down" => self.down = Self::parse_shortcuts(shortcuts),
-                            "up" => self.up = Self::parse_shortcuts(shortcuts),
-                            "left" => self.left = Self::parse_shortcuts(shortcuts),
-                            "right" => self.right = Self::parse_shortcuts(shortcuts),
-                            "open_article" => self.open_article = Self::parse_shortcuts(shortcuts),
-                            "refresh" => self.refresh = Self::parse_shortcuts(shortcuts),
-                            _ => {}
+                            Err(err) => errors.push((name.clone(), Some(key.clone()), err)),
                         }
                     }
                 }
                 "general" => {
                     for (key, value) in section_iter {
-                        match key.as_str() {
-                            "max_items" => {
-                                let max_items = value.parse::<u16>().unwrap_or_else(|_| {
-                                    panic!("{} is not a valid max_items value", value)
-                                });
-
-                                if max_items > 500 {
-                                    panic!("A max_items value greater than 500 is useless as the API returns a max of 500 posts");
-                                } else {
+                        let result = match key.as_str() {
+                            "max_items" => match value.parse::<u16>() {
+                                Ok(max_items) if max_items > 500 => Err(String::from(
+                                    "a max_items value greater than 500 is useless as the API returns a max of 500 posts",
+                                )),
+                                Ok(max_items) => {
                                     self.max_items = max_items;
+                                    Ok(())
                                 }
-                            }
+                                Err(_) => Err(format!("{} is not a valid max_items value", value)),
+                            },
                             "default_view" => {
-                                self.default_view = match value.to_lowercase().as_str() {
+                                let view = match value.to_lowercase().as_str() {
                                     "top" => MenuItem::Top,
                                     "new" => MenuItem::New,
+                                    "ask" => MenuItem::Ask,
+                                    "show" => MenuItem::Show,
+                                    "jobs" => MenuItem::Jobs,
+                                    "saved" => MenuItem::Saved,
                                     _ => {
-                                        eprintln!(
-                                            "{} is not a valid default_view value",
-                                            value.to_lowercase()
-                                        );
-                                        MenuItem::Top
+                                        errors.push((
+                                            name.clone(),
+                                            Some(key.clone()),
+                                            format!(
+                                                "{} is not a valid default_view value, expected {}",
+                                                value,
+                                                MenuItem::NAMES
+                                            ),
+                                        ));
+                                        continue;
                                     }
+                                };
+                                self.default_view = view;
+                                Ok(())
+                            }
+                            "scroll_past_list" => match value.parse::<bool>() {
+                                Ok(scroll_past_list) => {
+                                    self.scroll_past_list = scroll_past_list;
+                                    Ok(())
+                                }
+                                Err(_) => Err(format!(
+                                    "{} is not a valid scroll_past_list value, expected true or false",
+                                    value
+                                )),
+                            },
+                            "layout" => match value.parse() {
+                                Ok(layout) => {
+                                    self.layout = layout;
+                                    Ok(())
+                                }
+                                Err(err) => Err(err),
+                            },
+                            "split_layout" => match value.parse() {
+                                Ok(split_layout) => {
+                                    self.split_layout = split_layout;
+                                    Ok(())
+                                }
+                                Err(err) => Err(err),
+                            },
+                            "mouse" => match value.parse::<bool>() {
+                                Ok(mouse) => {
+                                    self.mouse = mouse;
+                                    Ok(())
+                                }
+                                Err(_) => Err(format!(
+                                    "{} is not a valid mouse value, expected true or false",
+                                    value
+                                )),
+                            },
+                            "api_url" => {
+                                self.api_url = value.trim_end_matches('/').to_string();
+                                Ok(())
+                            }
+                            "restore_session" => match value.parse::<bool>() {
+                                Ok(restore_session) => {
+                                    self.restore_session = restore_session;
+                                    Ok(())
+                                }
+                                Err(_) => Err(format!(
+                                    "{} is not a valid restore_session value, expected true or false",
+                                    value
+                                )),
+                            },
+                            "log_level" => match value.parse::<LevelFilter>() {
+                                Ok(log_level) => {
+                                    self.log_level = log_level;
+                                    Ok(())
                                 }
+                                Err(_) => Err(format!(
+                                    "{} is not a valid log_level value, expected off, error, warn, info, debug or trace",
+                                    value
+                                )),
+                            },
+                            _ => Err(String::from("unknown key")),
+                        };
+
+                        if let Err(err) = result {
+                            errors.push((name.clone(), Some(key.clone()), err));
+                        }
+                    }
+                }
+                "filters" => {
+                    for (key, value) in section_iter {
+                        let rules = match split_list(value) {
+                            Ok(rules) => rules,
+                            Err(err) => {
+                                errors.push((name.clone(), Some(key.clone()), err));
+                                continue;
+                            }
+                        };
+                        let rules = rules.iter().map(String::as_str);
+                        match key.as_str() {
+                            "domains" => {
+                                self.filters.domains = rules.map(str::to_lowercase).collect()
                             }
-                            "scroll_past_list" => {
-                                self.scroll_past_list = value.parse::<bool>().unwrap_or_else(|_| {
-                                    panic!("{} is not a valid scroll_past_list value", value);
-                                })
+                            "authors" => self.filters.authors = rules.map(String::from).collect(),
+                            "titles" => {
+                                self.filters.titles = rules
+                                    .filter_map(|rule| match Regex::new(rule) {
+                                        Ok(regex) => Some(regex),
+                                        Err(err) => {
+                                            errors.push((
+                                                name.clone(),
+                                                Some(key.clone()),
+                                                format!("{} is not a valid regex: {}", rule, err),
+                                            ));
+                                            None
+                                        }
+                                    })
+                                    .collect()
                             }
-                            _ => {}
+                            _ => errors.push((
+                                name.clone(),
+                                Some(key.clone()),
+                                String::from("unknown key"),
+                            )),
                         }
                     }
                 }
-                _ => {}
+                "highlight" => {
+                    self.highlights.rules = section_iter
+                        .filter_map(|(key, value)| match HighlightRule::parse(value) {
+                            Ok(mut rule) => {
+                                // Like the monochrome theme, only the modifiers are kept.
+                                if Theme::no_color() {
+                                    rule.style.fg = None;
+                                    rule.style.bg = None;
+                                }
+                                Some((key.clone(), rule))
+                            }
+                            Err(err) => {
+                                errors.push((name.clone(), Some(key.clone()), err));
+                                None
+                            }
+                        })
+                        .collect()
+                }
+                "theme" => {
+                    let items: Vec<_> = section_iter.collect();
+                    let theme_name = items
+                        .iter()
+                        .find(|(key, _)| key.as_str() == "name")
+                        .map(|(_, value)| value.as_str())
+                        .unwrap_or("default");
+
+                    // NO_COLOR only replaces the theme if none was chosen explicitly.
+                    let chosen = self
+                        .origins
+                        .contains_key(&(name.clone(), String::from("name")));
+                    self.theme = if Theme::no_color() && !chosen {
+                        Theme::named("monochrome").unwrap_or_default()
+                    } else {
+                        match Theme::named(theme_name) {
+                            Some(theme) => theme,
+                            None => {
+                                errors.push((
+                                    name.clone(),
+                                    Some(String::from("name")),
+                                    format!("{} is not a valid theme, expected default, light, solarized or monochrome", theme_name),
+                                ));
+                                Theme::default()
+                            }
+                        }
+                    };
+
+                    for (key, value) in items {
+                        if key.as_str() == "name" {
+                            continue;
+                        }
+                        if let Err(err) = self.theme.set(key, value) {
+                            errors.push((name.clone(), Some(key.clone()), err));
+                        }
+                    }
+                }
+                "rows" => {
+                    for (key, value) in section_iter {
+                        let line = match key.as_str() {
+                            "line1" => &mut lines[0],
+                            "line2" => &mut lines[1],
+                            "line3" => &mut lines[2],
+                            "compact" => &mut self.compact_row,
+                            _ => {
+                                errors.push((
+                                    name.clone(),
+                                    Some(key.clone()),
+                                    String::from("unknown key"),
+                                ));
+                                continue;
+                            }
+                        };
+
+                        match value.parse() {
+                            Ok(template) => *line = template,
+                            Err(err) => errors.push((name.clone(), Some(key.clone()), err)),
+                        }
+                    }
+                }
+                _ => errors.push((name.clone(), None, String::from("unknown section"))),
             }
         }
 
-        Ok(())
+        self.rows = lines.into_iter().filter(|line| !line.is_empty()).collect();
+        if self.rows.is_empty() {
+            errors.push((
+                String::from("rows"),
+                Some(String::from("line1")),
+                String::from("at least one line of the rows must be set"),
+            ));
+            self.rows = vec![template::DEFAULT_LINES[0].parse().unwrap_or_default()];
+        }
+        if self.compact_row.is_empty() {
+            self.compact_row = template::DEFAULT_COMPACT.parse().unwrap_or_default();
+        }
+
+        for (key, message) in self.conflicts() {
+            errors.push((String::from("keybindings"), Some(key.to_string()), message));
+        }
+
+        for (section, key, message) in errors {
+            let origin = match &key {
+                Some(key) => self
+                    .origins
+                    .get(&(section.clone(), key.clone()))
+                    .cloned()
+                    .unwrap_or(Origin::Default),
+                None => Origin::File(None),
+            };
+
+            self.errors.push(ConfigError {
+                section,
+                key,
+                origin,
+                message,
+            });
+        }
     }
 
     pub fn write(&self) -> Result<(), std::io::Error> {
@@ -184,49 +723,292 @@ impl Config {
         Ok(())
     }
 
-    fn config_path() -> PathBuf {
-        let mut config_dir = ProjectDirs::from("me", "bramw", "hntui")
-            .unwrap()
-            .config_dir()
-            .to_path_buf();
-        config_dir.push("config.ini");
-        config_dir
+    /// Returns the keys bound to the action.
+    pub fn keybinding(&self, action: Action) -> Option<&Keybinding> {
+        self.keybindings.get(&action)
+    }
+
+    /// Returns the action bound to exactly these keys.
+    pub fn action(&self, keys: &[crossterm::event::KeyEvent]) -> Option<Action> {
+        Action::ALL.iter().copied().find(|action| {
+            self.keybinding(*action)
+                .is_some_and(|keybinding| keybinding.matches(keys))
+        })
     }
 
-    fn parse_shortcuts(shortcuts: Vec<&str>) -> HashSet<KeyCode> {
-        shortcuts
+    /// Finds key sequences that are bound to two actions, or that can never complete because
+    /// a shorter sequence they start with is bound as well. A conflict is reported on the
+    /// binding that was set, rather than the default one it clashes with, and names where the
+    /// other binding comes from.
+    fn conflicts(&self) -> Vec<(&'static str, String)> {
+        let keybindings: Vec<(&str, &Keybinding)> = Action::ALL
             .iter()
-            .map(|shortcut| match *shortcut {
-                "backspace" => KeyCode::Backspace,
-                "enter" => KeyCode::Enter,
-                "arrow_left" => KeyCode::Left,
-                "arrow_right" => KeyCode::Right,
-                "arrow_up" => KeyCode::Up,
-                "arrow_down" => KeyCode::Down,
-                "home" => KeyCode::Home,
-                "end" => KeyCode::End,
-                "page_up" => KeyCode::PageUp,
-                "page_down" => KeyCode::PageDown,
-                "tab" => KeyCode::Tab,
-                "back_tab" => KeyCode::BackTab,
-                "delete" => KeyCode::Delete,
-                "insert" => KeyCode::Insert,
-                "esc" => KeyCode::Esc,
-                char => {
-                    if char.len() != 1 {
-                        if char.starts_with('f') {
-                            let mut key = char.to_string();
-                            key.remove(0);
-
-                            return KeyCode::F(key.parse::<u8>().unwrap_or_else(|err| {
-                                panic!("{}: {} is not a valid F key", err, key)
-                            }));
+            .filter_map(|action| Some((action.name(), self.keybinding(*action)?)))
+            .collect();
+        let origin = |action: &str| {
+            self.origins
+                .get(&(String::from("keybindings"), action.to_string()))
+                .cloned()
+                .unwrap_or(Origin::Default)
+        };
+        // Whether only the other binding of a conflict was set, so it's the one to report.
+        let other_was_set = |action: &str, other_action: &str| {
+            matches!(origin(action), Origin::Default)
+                && !matches!(origin(other_action), Origin::Default)
+        };
+        let mut conflicts = Vec::new();
+
+        for (index, (action, keybinding)) in keybindings.iter().enumerate() {
+            for sequence in &keybinding.sequences {
+                for (other_index, (other_action, other)) in keybindings.iter().enumerate() {
+                    for other_sequence in &other.sequences {
+                        let mut keys = String::new();
+                        let _ = keys::write_sequence(&mut keys, sequence);
+
+                        if sequence == other_sequence && index < other_index {
+                            let (action, other_action) = if other_was_set(action, other_action) {
+                                (*other_action, *action)
+                            } else {
+                                (*action, *other_action)
+                            };
+                            conflicts.push((
+                                action,
+                                format!(
+                                    "{} is also bound to {} ({})",
+                                    keys,
+                                    other_action,
+                                    origin(other_action)
+                                ),
+                            ));
+                        } else if other_sequence.len() < sequence.len()
+                            && sequence.starts_with(other_sequence)
+                        {
+                            let mut prefix = String::new();
+                            let _ = keys::write_sequence(&mut prefix, other_sequence);
+                            if other_was_set(action, other_action) {
+                                conflicts.push((
+                                    *other_action,
+                                    format!(
+                                        "{} keeps {} of {} from being used ({})",
+                                        prefix,
+                                        keys,
+                                        action,
+                                        origin(action)
+                                    ),
+                                ));
+                            } else {
+                                conflicts.push((
+                                    *action,
+                                    format!(
+                                        "{} can't be used as {} is bound to {} ({})",
+                                        keys,
+                                        prefix,
+                                        other_action,
+                                        origin(other_action)
+                                    ),
+                                ));
+                            }
                         }
-                        panic!("{} is not a valid shortcut", char);
                     }
-                    KeyCode::Char(char.chars().next().unwrap())
                 }
-            })
-            .collect()
+            }
+        }
+
+        conflicts
+    }
+
+    fn config_path() -> Result<PathBuf, std::io::Error> {
+        Ok(files::project_dirs()?.config_dir().join("config.ini"))
+    }
+}
+
+/// Splits a list like `a, b,c` on its commas, trimming the items and leaving out empty ones.
+/// Items with commas, like some regexes, can be quoted, like `"a, b"` or `title:"a, b"`, with
+/// `\"` for a quote inside the quotes.
+pub fn split_list(value: &str) -> Result<Vec<String>, String> {
+    let mut items = Vec::new();
+    // The characters of the current item, and whether they were quoted, so only the
+    // whitespace around the item is trimmed.
+    let mut item = Vec::new();
+    let mut quoted = false;
+
+    let mut chars = value.chars().peekable();
+    while let Some(char) = chars.next() {
+        match char {
+            '"' => quoted = !quoted,
+            '\\' if quoted && chars.peek() == Some(&'"') => {
+                chars.next();
+                item.push(('"', true));
+            }
+            ',' if !quoted => items.extend(take_list_item(&mut item)),
+            char => item.push((char, quoted)),
+        }
+    }
+    if quoted {
+        return Err(format!("{} has a quote that isn't closed", value));
+    }
+    items.extend(take_list_item(&mut item));
+
+    Ok(items)
+}
+
+/// Takes the characters of the item without the whitespace around it, if there are any left.
+fn take_list_item(item: &mut Vec<(char, bool)>) -> Option<String> {
+    let chars = std::mem::take(item);
+    let kept = |(char, quoted): &(char, bool)| *quoted || !char.is_whitespace();
+    let start = chars.iter().position(kept)?;
+    let end = chars.iter().rposition(kept)?;
+    Some(chars[start..=end].iter().map(|(char, _)| char).collect())
+}
+
+/// Joins the items into a list that `split_list` reads back the same, quoting the items that
+/// need it.
+pub fn join_list<I>(items: I) -> String
+where
+    I: IntoIterator,
+    I::Item: AsRef<str>,
+{
+    items
+        .into_iter()
+        .map(|item| {
+            let item = item.as_ref();
+            if item.contains(',') || item.contains('"') || item.trim() != item {
+                format!("\"{}\"", item.replace('"', "\\\""))
+            } else {
+                item.to_string()
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Writes the contents to a config file of its own, in a directory named after the test.
+    fn config_file(name: &str, contents: &str) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("hntui-config-{}-{}", name, std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("config.ini");
+        std::fs::write(&path, contents).unwrap();
+        path
+    }
+
+    fn errors(config: &Config) -> Vec<String> {
+        config.errors.iter().map(ToString::to_string).collect()
+    }
+
+    #[test]
+    fn splits_and_joins_lists() {
+        assert_eq!(split_list(" a, b ,,c ").unwrap(), ["a", "b", "c"]);
+        assert_eq!(
+            split_list(r#""a, b", " c ", "say \"hi\"""#).unwrap(),
+            ["a, b", " c ", r#"say "hi""#]
+        );
+        assert!(split_list("").unwrap().is_empty());
+        assert_eq!(
+            split_list(r#"a, "b, c"#).unwrap_err(),
+            r#"a, "b, c has a quote that isn't closed"#
+        );
+
+        let items = ["a", "b, c", " d", r#"say "hi""#];
+        let joined = join_list(&items);
+        assert_eq!(joined, r#"a, "b, c", " d", "say \"hi\"""#);
+        assert_eq!(split_list(&joined).unwrap(), items);
+    }
+
+    #[test]
+    fn layers_the_file_environment_and_command_line() {
+        let path = config_file(
+            "layers",
+            "[general]\nmax_items = 10\nmouse = false\nscroll_past_list = false\n",
+        );
+        let vars = || {
+            vec![
+                (String::from("HNTUI_GENERAL_MAX_ITEMS"), String::from("20")),
+                (String::from("HNTUI_GENERAL_MOUSE"), String::from("true")),
+                (
+                    String::from("HNTUI_API_URL"),
+                    String::from("http://localhost"),
+                ),
+                (String::from("HOME"), String::from("/home/user")),
+            ]
+        };
+        let overrides = [(
+            String::from("general"),
+            String::from("max_items"),
+            String::from("40"),
+        )];
+
+        let config = Config::load_with(Some(path.clone()), &[], vars()).unwrap();
+        assert_eq!(config.max_items, 20);
+        assert!(config.mouse);
+        assert!(!config.scroll_past_list);
+        assert_eq!(config.api_url, "http://localhost");
+        assert_eq!(config.default_view, MenuItem::Top);
+        assert!(config.errors.is_empty(), "{:?}", config.errors);
+
+        let config = Config::load_with(Some(path.clone()), &overrides, vars()).unwrap();
+        assert_eq!(config.max_items, 40);
+        assert!(config.mouse);
+
+        let config = Config::load_with(Some(path.clone()), &[], Vec::new()).unwrap();
+        assert_eq!(config.max_items, 10);
+        assert!(!config.mouse);
+
+        std::fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn reports_the_line_of_invalid_values() {
+        let path = config_file(
+            "lines",
+            "[general]\nmax_items = 30\n\nlayout = sideways\n\n[theme]\nname = plain\n",
+        );
+
+        let config = Config::load_with(Some(path.clone()), &[], Vec::new()).unwrap();
+        assert_eq!(config.layout, LayoutMode::Auto);
+        assert_eq!(errors(&config).len(), 2, "{:?}", errors(&config));
+        assert!(matches!(config.errors[0].origin, Origin::File(Some(4))));
+        assert!(errors(&config)[0].starts_with("line 4: [general] layout: "));
+        assert!(errors(&config)[1].starts_with("line 7: [theme] name: "));
+
+        std::fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn reports_the_line_of_a_broken_file() {
+        let path = config_file("broken", "[general]\nmax_items = 10\n[theme\n");
+
+        let config = Config::load_with(Some(path.clone()), &[], Vec::new()).unwrap();
+        assert_eq!(config.max_items, 30);
+        assert!(matches!(config.errors[0].origin, Origin::File(Some(3))));
+        assert!(errors(&config)[0].ends_with(", using the default config"));
+
+        std::fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn reload_keeps_the_config_when_the_file_is_broken() {
+        let path = config_file("reload", "[general]\nmouse = false\n");
+        let config = Config::load_with(Some(path.clone()), &[], Vec::new()).unwrap();
+        assert!(!config.mouse);
+
+        std::fs::write(&path, "[general]\nmouse = true\n[theme\n").unwrap();
+        let err = config.reload().unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+        assert_eq!(
+            err.to_string(),
+            "Incorrect section syntax at line 3, keeping the current config"
+        );
+
+        std::fs::write(&path, "[general]\nmouse = true\n").unwrap();
+        assert!(config.reload().unwrap().mouse);
+
+        std::fs::remove_dir_all(path.parent().unwrap()).unwrap();
+        let err = config.reload().unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::NotFound);
     }
 }
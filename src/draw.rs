@@ -0,0 +1,552 @@
+use crate::{
+    actions::{self, Action},
+    app::{feed_changes, App, Overlay, PromptKind},
+    bookmarks::Bookmarks,
+    config::Config,
+    export, get_time_offset, keys,
+    notifications::Level,
+    pane::{thread_lines, Focus, Pane},
+    theme::Theme,
+    ui::{self, LayoutMode, MenuItem, SplitLayout},
+};
+use api::Post;
+use std::convert::TryFrom;
+use time::OffsetDateTime;
+use tui::{
+    backend::CrosstermBackend,
+    layout::{Constraint, Direction, Layout, Rect},
+    style::{Modifier, Style},
+    symbols::DOT,
+    terminal::Frame,
+    text::{Span, Spans, Text},
+    widgets::{Block, Borders, Clear, List, Paragraph, Tabs, Wrap},
+};
+use unicode_width::UnicodeWidthStr;
+
+/// Describes the post in full for the preview pane of the wide layout.
+fn preview(
+    post: &Post,
+    current_time: OffsetDateTime,
+    bookmarks: &Bookmarks,
+    theme: &Theme,
+) -> Text<'static> {
+    let mut lines = vec![Spans::from(Span::styled(
+        post.title().to_string(),
+        theme.title.add_modifier(Modifier::BOLD),
+    ))];
+
+    if !post.url().is_empty() {
+        lines.push(Spans::from(Span::styled(
+            post.url().to_string(),
+            theme.domain,
+        )));
+    }
+
+    lines.push(Spans::from(Span::styled(
+        format!(
+            "{} points by {} | {} | {} comments",
+            post.score(),
+            post.by(),
+            get_time_offset(current_time, post.time()),
+            post.descendants()
+        ),
+        theme.metadata,
+    )));
+
+    if let Some(bookmark) = bookmarks.get(post.id()) {
+        if !bookmark.tags.is_empty() {
+            lines.push(Spans::from(Span::styled(
+                format!("Tags: {}", bookmark.tags.join(", ")),
+                theme.tags,
+            )));
+        }
+        if !bookmark.note.is_empty() {
+            lines.push(Spans::from(Span::styled(
+                format!("Note: {}", bookmark.note),
+                theme.note,
+            )));
+        }
+    }
+
+    let text = export::render_html(post.text(), false);
+    if !text.is_empty() {
+        lines.push(Spans::default());
+        lines.extend(text.lines().map(|line| Spans::from(line.to_string())));
+    }
+
+    Text::from(lines)
+}
+
+/// Draws the tabs, the panes, the status bar or the prompt, and the help on top of them if it
+/// is open.
+pub fn draw(frame: &mut Frame<CrosstermBackend<std::io::Stdout>>, app: &mut App) {
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(3),
+            Constraint::Min(2),
+            Constraint::Length(app.notifications.banner.is_some() as u16),
+            Constraint::Length(1),
+        ])
+        .split(frame.size());
+
+    let feed = app.feed();
+    let mut title = format!(" Sorted by {} ", app.sort_mode);
+    if !feed.muted.is_empty() {
+        title.push_str(&format!(
+            "{} {} hidden{} ",
+            DOT,
+            feed.muted.len(),
+            if app.show_hidden { " (shown)" } else { "" }
+        ));
+    }
+    let tab_block = Block::default()
+        .borders(Borders::TOP | Borders::LEFT | Borders::RIGHT)
+        .title(title);
+
+    let tabs: Vec<Spans> = MenuItem::ALL
+        .iter()
+        .map(|tab| Spans::from(tab.to_string()))
+        .collect();
+    let tab = Tabs::new(tabs)
+        .select(feed.feed.into())
+        .block(tab_block)
+        .style(app.config.theme.tab)
+        .highlight_style(app.config.theme.active_tab)
+        .divider(DOT);
+
+    frame.render_widget(tab, chunks[0]);
+
+    if let Some(banner) = &app.notifications.banner {
+        let theme = &app.config.theme;
+        let mut spans = vec![Span::styled(format!(" {}", banner), theme.status)];
+        if let Some(keys) = key_hint(&app.config, Action::DismissBanner) {
+            let hint = vec![
+                Span::styled(format!(" {}", keys), theme.key),
+                Span::raw(" dismiss "),
+            ];
+            let room =
+                (chunks[2].width as usize).saturating_sub(hint.iter().map(Span::width).sum());
+            ui::fit_span(&mut spans, 0, room);
+            spans.extend(hint);
+        }
+        frame.render_widget(
+            Paragraph::new(Spans::from(spans)).style(theme.status_bar),
+            chunks[2],
+        );
+    }
+    app.tabs_area = chunks[0];
+
+    match app.split {
+        None => draw_feed(frame, app, Focus::Main, chunks[1]),
+        Some(ref split) => {
+            let direction = match app.config.split_layout.for_width(chunks[1].width) {
+                SplitLayout::SideBySide => Direction::Horizontal,
+                _ => Direction::Vertical,
+            };
+            let thread = matches!(split, Pane::Thread(_));
+            let panes = Layout::default()
+                .direction(direction)
+                .constraints([Constraint::Percentage(50), Constraint::Percentage(50)])
+                .split(chunks[1]);
+
+            draw_feed(frame, app, Focus::Main, panes[0]);
+            if thread {
+                draw_thread(frame, app, panes[1]);
+            } else {
+                draw_feed(frame, app, Focus::Split, panes[1]);
+            }
+        }
+    }
+
+    if let Some(prompt) = &app.prompt {
+        let text = format!("{}{}", prompt.label, prompt.input);
+        frame.set_cursor(chunks[3].x + text.width() as u16, chunks[3].y);
+
+        // Shows which command the possibly abbreviated name in the palette will run.
+        let completion = match prompt.kind {
+            PromptKind::Command => prompt
+                .input
+                .split_whitespace()
+                .next()
+                .and_then(actions::complete),
+            _ => None,
+        };
+        let mut spans = vec![Span::raw(text)];
+        if let Some(command) = completion {
+            let description = match Action::from_name(command) {
+                Some(action) => format!(" {} {}", DOT, action.description()),
+                None => String::new(),
+            };
+            spans.push(Span::styled(
+                format!("  {}{}", command, description),
+                app.config.theme.hint,
+            ));
+        }
+        frame.render_widget(Paragraph::new(Spans::from(spans)), chunks[3]);
+    } else {
+        frame.render_widget(
+            Paragraph::new(status_bar(app, chunks[3].width as usize))
+                .style(app.config.theme.status_bar),
+            chunks[3],
+        );
+    }
+
+    if let Some((overlay, scroll)) = app.overlay {
+        let (title, lines) = match overlay {
+            Overlay::Help => (" Help ", help(&app.config)),
+            Overlay::Log => (" Log ", log(app)),
+        };
+        let width =
+            (lines.iter().map(Spans::width).max().unwrap_or(0) as u16 + 2).min(frame.size().width);
+        // Lines wider than the screen are wrapped onto more rows.
+        let inner_width = width.saturating_sub(2).max(1);
+        let rows: u16 = lines
+            .iter()
+            .map(|line| (line.width() as u16).max(1).div_ceil(inner_width))
+            .sum();
+        let area = ui::centered(frame.size(), width, rows + 2);
+
+        // Keeps the last row at the bottom when scrolled too far.
+        let scroll = scroll.min(rows.saturating_sub(area.height.saturating_sub(2)));
+        app.overlay = Some((overlay, scroll));
+
+        let paragraph = Paragraph::new(lines)
+            .block(Block::default().borders(Borders::ALL).title(title))
+            .wrap(Wrap { trim: false })
+            .scroll((scroll, 0));
+        frame.render_widget(Clear, area);
+        frame.render_widget(paragraph, area);
+    }
+
+    if let Some(notification) = app
+        .notifications
+        .current()
+        .filter(|notification| notification.level == Level::Error)
+    {
+        let size = frame.size();
+        let width = (notification.message.width() as u16 + 4).clamp(30, 72);
+        // The message is wrapped, which can take a line more than its width suggests.
+        let lines = notification.message.width() as u16 / width.saturating_sub(4).max(1) + 2;
+        let area = ui::centered(size, width, lines + 4);
+
+        let text = vec![
+            Spans::from(notification.message.clone()),
+            Spans::default(),
+            Spans::from(Span::styled(
+                "Press any key to close",
+                app.config.theme.hint,
+            )),
+        ];
+        let popup = Paragraph::new(text)
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .border_style(app.config.theme.error)
+                    .title(Span::styled(" Error ", app.config.theme.error)),
+            )
+            .wrap(Wrap { trim: true });
+        frame.render_widget(Clear, area);
+        frame.render_widget(popup, area);
+    }
+}
+
+/// Draws the list of the feed in the pane, with the preview of the wide layout next to it if
+/// the view isn't split. Split panes get a border all around, highlighted when focused.
+fn draw_feed(
+    frame: &mut Frame<CrosstermBackend<std::io::Stdout>>,
+    app: &mut App,
+    pane: Focus,
+    area: Rect,
+) {
+    let split = app.split.is_some();
+    let layout = match app.config.layout.for_width(area.width) {
+        // A preview next to half of the screen leaves too little room for either.
+        LayoutMode::Wide if split => LayoutMode::Normal,
+        layout => layout,
+    };
+    let (list_area, preview_area, borders) = if split {
+        (area, None, Borders::ALL)
+    } else if layout == LayoutMode::Wide {
+        let columns = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([Constraint::Percentage(60), Constraint::Percentage(40)])
+            .split(area);
+        (
+            columns[0],
+            Some(columns[1]),
+            Borders::BOTTOM | Borders::LEFT,
+        )
+    } else {
+        (area, None, Borders::BOTTOM | Borders::LEFT | Borders::RIGHT)
+    };
+
+    let mut block = Block::default().borders(borders);
+    if split {
+        let style = pane_style(app, pane);
+        let title = format!(" {} ", app.feed_pane(pane).feed);
+        block = block.border_style(style).title(Span::styled(title, style));
+    }
+    let inner = block.inner(list_area);
+
+    let feed = match (pane, &mut app.split) {
+        (Focus::Split, Some(Pane::Feed(feed))) => feed,
+        _ => &mut app.main,
+    };
+    if layout != feed.layout || inner.width != feed.list_width {
+        feed.layout = layout;
+        feed.list_width = inner.width;
+        let changes = feed_changes(feed.feed, &app.items);
+        feed.update_list(
+            app.current_time,
+            &app.config,
+            &app.history,
+            &app.bookmarks,
+            changes,
+        );
+    }
+
+    feed.list_height = inner.height;
+    feed.list_area = inner;
+    feed.stateful_list.update_offset(feed.list_height as usize);
+
+    let (items, mut state) = feed.stateful_list.visible(feed.list_height as usize);
+    let list = List::new(items)
+        .block(block)
+        .highlight_style(app.config.theme.selected);
+    frame.render_stateful_widget(list, list_area, &mut state);
+
+    if let Some(area) = preview_area {
+        let text = match app.main.selected_post() {
+            Some(post) => preview(post, app.current_time, &app.bookmarks, &app.config.theme),
+            None => Text::default(),
+        };
+        let preview = Paragraph::new(text)
+            .block(Block::default().borders(Borders::BOTTOM | Borders::LEFT | Borders::RIGHT))
+            .wrap(Wrap { trim: false });
+        frame.render_widget(preview, area);
+    }
+}
+
+/// Draws the comment thread of the second pane, scrolled to where it was left.
+fn draw_thread(frame: &mut Frame<CrosstermBackend<std::io::Stdout>>, app: &mut App, area: Rect) {
+    let style = pane_style(app, Focus::Split);
+    let thread = match &mut app.split {
+        Some(Pane::Thread(thread)) => thread,
+        _ => return,
+    };
+
+    let title = match thread.thread.post.title() {
+        "" => String::from(" Comments "),
+        title => format!(" Comments: {} ", title),
+    };
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_style(style)
+        .title(Span::styled(
+            ui::truncate(&title, area.width.saturating_sub(2) as usize),
+            style,
+        ));
+    let inner = block.inner(area);
+
+    if inner.width != thread.width {
+        let (lines, starts) = thread_lines(
+            &thread.thread,
+            &thread.collapsed,
+            app.current_time,
+            &app.config.theme,
+            inner.width as usize,
+        );
+        thread.lines = lines;
+        thread.starts = starts;
+        thread.width = inner.width;
+    }
+    thread.area = area;
+    thread.height = inner.height;
+
+    // Keeps the last line at the bottom when scrolled too far.
+    let end =
+        u16::try_from(thread.lines.len().saturating_sub(inner.height as usize)).unwrap_or(u16::MAX);
+    thread.scroll = thread.scroll.min(end);
+
+    // The selected post stands out by the line it starts on.
+    let selected = thread.starts.get(thread.selected).copied();
+    let selected_style = app.config.theme.selected;
+    let lines: Vec<Spans> = thread
+        .lines
+        .iter()
+        .enumerate()
+        .skip(thread.scroll as usize)
+        .take(inner.height as usize)
+        .map(|(index, line)| match selected {
+            Some(selected) if selected == index => Spans::from(
+                line.0
+                    .iter()
+                    .map(|span| {
+                        Span::styled(span.content.clone(), span.style.patch(selected_style))
+                    })
+                    .collect::<Vec<_>>(),
+            ),
+            _ => line.clone(),
+        })
+        .collect();
+    frame.render_widget(Paragraph::new(lines).block(block), area);
+}
+
+/// Returns the style of the border of the pane, which stands out when it has the focus.
+fn pane_style(app: &App, pane: Focus) -> Style {
+    if app.focus == pane {
+        app.config.theme.active_tab
+    } else {
+        app.config.theme.tab
+    }
+}
+
+/// The status bar shows the last message, or hints for the most important keys, on the left
+/// and the feed, the selection and when the feed was refreshed, or which post of the thread
+/// is selected, on the right.
+fn status_bar(app: &App, width: usize) -> Spans<'static> {
+    let theme = &app.config.theme;
+
+    let feed = app.feed();
+    let fetched = app.items.get(&feed.feed).and_then(|items| items.fetched);
+    let mut right = match app.thread() {
+        Some(thread) => format!(
+            " Comments {} {}/{}",
+            DOT,
+            thread.selected + 1,
+            thread.posts().len()
+        ),
+        None => format!(
+            " {} {} {}/{}",
+            feed.feed,
+            DOT,
+            feed.stateful_list
+                .selected_post()
+                .map_or(0, |post| post + 1),
+            feed.posts.len()
+        ),
+    };
+    if app.loading {
+        right.push_str(&format!(" {} loading…", DOT));
+    } else if let Some(fetched) = fetched.filter(|_| app.thread().is_none()) {
+        let ago = get_time_offset(OffsetDateTime::now_utc(), fetched);
+        right.push_str(&format!(
+            " {} refreshed {}",
+            DOT,
+            if ago.is_empty() { "just now" } else { &ago }
+        ));
+    }
+    right.push(' ');
+
+    let message = app
+        .notifications
+        .current()
+        .filter(|notification| notification.level != Level::Error);
+    let mut left = match message {
+        Some(notification) => vec![Span::styled(
+            format!(" {}", notification.message),
+            theme.status,
+        )],
+        None => [
+            (Action::Help, "help"),
+            (Action::CommandPalette, "commands"),
+            (Action::Quit, "quit"),
+        ]
+        .iter()
+        .filter_map(|(action, label)| {
+            let keys = key_hint(&app.config, *action)?;
+            Some(vec![
+                Span::styled(format!(" {}", keys), theme.key),
+                Span::raw(format!(" {} ", label)),
+            ])
+        })
+        .flatten()
+        .collect(),
+    };
+
+    // The message is shortened if it doesn't fit next to the right side.
+    let room = width.saturating_sub(right.width());
+    if let Some(last) = left.len().checked_sub(1) {
+        ui::fit_span(&mut left, last, room);
+    }
+    let used: usize = left.iter().map(Span::width).sum();
+    left.push(Span::raw(" ".repeat(room.saturating_sub(used))));
+    left.push(Span::raw(right));
+
+    Spans::from(left)
+}
+
+/// Returns the first keys bound to the action, to show them as a hint.
+fn key_hint(config: &Config, action: Action) -> Option<String> {
+    let sequence = config.keybinding(action)?.sequences.first()?;
+    let mut hint = String::new();
+    keys::write_sequence(&mut hint, sequence).ok()?;
+    Some(hint)
+}
+
+/// Lists the notifications of the session, oldest first.
+fn log(app: &App) -> Vec<Spans<'static>> {
+    if app.notifications.log.is_empty() {
+        return vec![Spans::from(" No messages yet ")];
+    }
+
+    let now = OffsetDateTime::now_utc();
+    app.notifications
+        .log
+        .iter()
+        .map(|notification| {
+            let ago = get_time_offset(now, notification.time);
+            let level_style = match notification.level {
+                Level::Error => app.config.theme.error,
+                Level::Warning => app.config.theme.status,
+                Level::Info => Style::default(),
+            };
+            Spans::from(vec![
+                Span::styled(
+                    format!(" {:>18} ", if ago.is_empty() { "just now" } else { &ago }),
+                    app.config.theme.hint,
+                ),
+                Span::styled(format!("{:7} ", notification.level), level_style),
+                Span::raw(format!("{} ", notification.message)),
+            ])
+        })
+        .collect()
+}
+
+/// Lists every action with the keys currently bound to it and its name in the command palette.
+fn help(config: &Config) -> Vec<Spans<'static>> {
+    let keys: Vec<String> = Action::ALL
+        .iter()
+        .map(|action| {
+            config
+                .keybinding(*action)
+                .map(ToString::to_string)
+                .unwrap_or_default()
+        })
+        .collect();
+    let keys_width = keys.iter().map(|keys| keys.width()).max().unwrap_or(0);
+    let name_width = Action::ALL
+        .iter()
+        .map(|action| action.name().len())
+        .max()
+        .unwrap_or(0);
+
+    Action::ALL
+        .iter()
+        .zip(keys)
+        .map(|(action, keys)| {
+            Spans::from(vec![
+                Span::styled(
+                    format!(" {}{} ", keys, " ".repeat(keys_width - keys.width())),
+                    config.theme.key,
+                ),
+                Span::styled(
+                    format!(" {:width$} ", action.name(), width = name_width),
+                    config.theme.hint,
+                ),
+                Span::raw(format!(" {} ", action.description())),
+            ])
+        })
+        .collect()
+}
@@ -0,0 +1,553 @@
+use crate::extract_url;
+use api::{Post, Thread, User};
+use lazy_static::lazy_static;
+use regex::{Captures, Regex};
+use serde::Serialize;
+use std::{fmt::Write, path::Path, str::FromStr};
+
+lazy_static! {
+    static ref LINK_REGEX: Regex =
+        Regex::new(r#"<a\s+href="(?P<href>[^"]*)"[^>]*>(?P<text>.*?)</a>"#).unwrap();
+    static ref CODE_REGEX: Regex =
+        Regex::new(r"(?s)(?:<p>)?\s*<pre><code>(?P<code>.*?)</code></pre>\s*(?:<p>)?").unwrap();
+    static ref TAG_REGEX: Regex = Regex::new(r"<[^>]*>").unwrap();
+    static ref ENTITY_REGEX: Regex = Regex::new(r"&(?P<entity>#?[xX]?[0-9a-zA-Z]+);").unwrap();
+}
+
+const ITEM_URL: &str = "https://news.ycombinator.com/item?id=";
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Format {
+    Json,
+    Csv,
+    Markdown,
+    Text,
+}
+
+impl FromStr for Format {
+    type Err = String;
+
+    fn from_str(format: &str) -> Result<Self, Self::Err> {
+        match format.to_lowercase().as_str() {
+            "json" => Ok(Format::Json),
+            "csv" => Ok(Format::Csv),
+            "md" | "markdown" => Ok(Format::Markdown),
+            "txt" | "text" => Ok(Format::Text),
+            _ => Err(format!(
+                "{} is not a valid format, expected json, csv, md or text",
+                format
+            )),
+        }
+    }
+}
+
+impl Format {
+    /// Guesses the format from the extension of the path.
+    pub fn from_path(path: &Path) -> Option<Format> {
+        path.extension()?.to_str()?.parse().ok()
+    }
+}
+
+#[derive(Serialize)]
+struct RankedPost<'a> {
+    rank: usize,
+    item: &'a Post,
+}
+
+/// Renders the posts of a feed. The title is only used as the heading of Markdown exports.
+pub fn export_feed(
+    title: &str,
+    items: &[(usize, Post)],
+    format: Format,
+) -> Result<String, Box<dyn std::error::Error>> {
+    Ok(match format {
+        Format::Json => serde_json::to_string_pretty(
+            &items
+                .iter()
+                .map(|(rank, item)| RankedPost { rank: *rank, item })
+                .collect::<Vec<_>>(),
+        )?,
+        Format::Csv => {
+            let mut csv = String::from("rank,id,type,title,url,by,score,comments,time,hn_url\n");
+            for (rank, post) in items {
+                writeln!(
+                    csv,
+                    "{},{},{},{},{},{},{},{},{},{}{}",
+                    rank,
+                    post.id(),
+                    post_type(post),
+                    csv_field(post.title()),
+                    csv_field(post.url()),
+                    csv_field(post.by()),
+                    post.score(),
+                    post.descendants(),
+                    post.time().unix_timestamp(),
+                    ITEM_URL,
+                    post.id()
+                )?;
+            }
+            csv
+        }
+        Format::Markdown => {
+            let mut markdown = format!("# {}\n\n", title);
+            for (rank, post) in items {
+                writeln!(markdown, "{}. {}", rank, post_title(post, true))?;
+                writeln!(markdown, "   {}", post_metadata(post, true))?;
+            }
+            markdown
+        }
+        Format::Text => {
+            let mut text = String::new();
+            for (rank, post) in items {
+                writeln!(text, "{}. {}", rank, post_title(post, false))?;
+                writeln!(text, "   {}", post_metadata(post, false))?;
+            }
+            text
+        }
+    })
+}
+
+/// Renders a post and all of its replies.
+pub fn export_thread(
+    thread: &Thread,
+    format: Format,
+) -> Result<String, Box<dyn std::error::Error>> {
+    Ok(match format {
+        Format::Json => serde_json::to_string_pretty(thread)?,
+        Format::Csv => {
+            let mut csv = String::from("id,parent,depth,by,time,text\n");
+            write_csv_thread(&mut csv, thread, None, 0)?;
+            csv
+        }
+        Format::Markdown | Format::Text => {
+            let markdown = format == Format::Markdown;
+            let mut output = if markdown {
+                format!(
+                    "# {}\n\n{}\n\n",
+                    post_title(&thread.post, true),
+                    post_metadata(&thread.post, true)
+                )
+            } else {
+                format!(
+                    "{}\n{}\n\n",
+                    post_title(&thread.post, false),
+                    post_metadata(&thread.post, false)
+                )
+            };
+
+            let text = post_text(&thread.post, markdown);
+            if !text.is_empty() {
+                writeln!(output, "{}\n", text)?;
+            }
+
+            for reply in &thread.replies {
+                write_text_thread(&mut output, reply, 0, markdown)?;
+            }
+            output
+        }
+    })
+}
+
+pub fn export_user(user: &User, format: Format) -> Result<String, Box<dyn std::error::Error>> {
+    let joined = user.created.format("%Y-%m-%d");
+
+    Ok(match format {
+        Format::Json => serde_json::to_string_pretty(user)?,
+        Format::Csv => format!(
+            "id,created,karma,submitted,about\n{},{},{},{},{}\n",
+            csv_field(&user.id),
+            user.created.unix_timestamp(),
+            user.karma,
+            user.submitted.len(),
+            csv_field(&render_html(&user.about, false))
+        ),
+        Format::Markdown => format!(
+            "# [{}](https://news.ycombinator.com/user?id={})\n\n{} karma | joined {} | {} submissions\n\n{}\n",
+            user.id,
+            user.id,
+            user.karma,
+            joined,
+            user.submitted.len(),
+            render_html(&user.about, true)
+        ),
+        Format::Text => format!(
+            "{}\n{} karma | joined {} | {} submissions\n\n{}\n",
+            user.id,
+            user.karma,
+            joined,
+            user.submitted.len(),
+            render_html(&user.about, false)
+        ),
+    })
+}
+
+/// Converts the HTML used in the text of comments and posts to plain text, or to Markdown
+/// if `markdown` is set.
+pub fn render_html(html: &str, markdown: bool) -> String {
+    // Code blocks are set apart with blank lines of their own, so the paragraph breaks and
+    // newlines around them are dropped.
+    let text = CODE_REGEX.replace_all(html, |captures: &Captures| {
+        if markdown {
+            format!("\n\n```\n{}\n```\n\n", &captures["code"].trim_end())
+        } else {
+            format!("\n\n{}\n\n", &captures["code"].trim_end())
+        }
+    });
+
+    let text = text.replace("<p>", "\n\n");
+
+    let text = LINK_REGEX.replace_all(&text, |captures: &Captures| {
+        let href = &captures["href"];
+        let text = &captures["text"];
+        if markdown {
+            format!("[{}]({})", text, href)
+        } else if decode_entities(text) == decode_entities(href) {
+            text.to_string()
+        } else {
+            format!("{} ({})", text, href)
+        }
+    });
+
+    let text = if markdown {
+        text.replace("<i>", "*").replace("</i>", "*")
+    } else {
+        text.into_owned()
+    };
+
+    decode_entities(&TAG_REGEX.replace_all(&text, ""))
+        .trim()
+        .to_string()
+}
+
+fn decode_entities(text: &str) -> String {
+    ENTITY_REGEX
+        .replace_all(text, |captures: &Captures| {
+            let entity = &captures["entity"];
+            let decoded = match entity {
+                "amp" => Some('&'),
+                "lt" => Some('<'),
+                "gt" => Some('>'),
+                "quot" => Some('"'),
+                "apos" => Some('\''),
+                "nbsp" => Some(' '),
+                _ => {
+                    if let Some(hex) = entity
+                        .strip_prefix("#x")
+                        .or_else(|| entity.strip_prefix("#X"))
+                    {
+                        u32::from_str_radix(hex, 16).ok().and_then(char::from_u32)
+                    } else if let Some(decimal) = entity.strip_prefix('#') {
+                        decimal.parse().ok().and_then(char::from_u32)
+                    } else {
+                        None
+                    }
+                }
+            };
+
+            match decoded {
+                Some(c) => c.to_string(),
+                None => captures[0].to_string(),
+            }
+        })
+        .into_owned()
+}
+
+fn post_type(post: &Post) -> &'static str {
+    match post {
+        Post::Job(_) => "job",
+        Post::Story(_) => "story",
+        Post::Comment(_) => "comment",
+        Post::Poll(_) => "poll",
+        Post::PollOpt(_) => "pollopt",
+    }
+}
+
+fn format_time(post: &Post) -> String {
+    post.time().format("%Y-%m-%d %H:%M UTC")
+}
+
+fn csv_field(field: &str) -> String {
+    if field.contains(&[',', '"', '\n', '\r'][..]) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+fn post_title(post: &Post, markdown: bool) -> String {
+    let title = if post.title().is_empty() {
+        format!("Item {}", post.id())
+    } else {
+        post.title().to_string()
+    };
+
+    match (extract_url(post.url()), markdown) {
+        (Some(domain), true) => format!("[{}]({}) ({})", title, post.url(), domain),
+        (None, true) => format!("[{}]({}{})", title, ITEM_URL, post.id()),
+        (Some(domain), false) => format!("{} ({})", title, domain),
+        (None, false) => title,
+    }
+}
+
+fn post_metadata(post: &Post, markdown: bool) -> String {
+    let points = post.score();
+    let descendants = post.descendants();
+    let comments = format!(
+        "{} {}",
+        descendants,
+        if descendants == 1 {
+            "comment"
+        } else {
+            "comments"
+        }
+    );
+
+    format!(
+        "{} {} by {} | {} | {}",
+        points,
+        if points == 1 { "point" } else { "points" },
+        post_author(post),
+        format_time(post),
+        if markdown {
+            format!("[{}]({}{})", comments, ITEM_URL, post.id())
+        } else {
+            format!("{} | {}{}", comments, ITEM_URL, post.id())
+        }
+    )
+}
+
+/// Returns the author of the post, or why it has none, like `post_text` does for the text.
+fn post_author(post: &Post) -> &str {
+    match post {
+        post if !post.by().is_empty() => post.by(),
+        Post::Comment(comment) if comment.dead => "[dead]",
+        _ => "[deleted]",
+    }
+}
+
+pub fn post_text(post: &Post, markdown: bool) -> String {
+    match post {
+        Post::Comment(comment) if comment.deleted => String::from("[deleted]"),
+        Post::Comment(comment) if comment.dead => String::from("[dead]"),
+        post => render_html(post.text(), markdown),
+    }
+}
+
+fn write_csv_thread(
+    csv: &mut String,
+    thread: &Thread,
+    parent: Option<u32>,
+    depth: usize,
+) -> std::fmt::Result {
+    writeln!(
+        csv,
+        "{},{},{},{},{},{}",
+        thread.post.id(),
+        parent.map(|parent| parent.to_string()).unwrap_or_default(),
+        depth,
+        csv_field(thread.post.by()),
+        thread.post.time().unix_timestamp(),
+        csv_field(&post_text(&thread.post, false))
+    )?;
+
+    for reply in &thread.replies {
+        write_csv_thread(csv, reply, Some(thread.post.id()), depth + 1)?;
+    }
+
+    Ok(())
+}
+
+fn write_text_thread(
+    output: &mut String,
+    thread: &Thread,
+    depth: usize,
+    markdown: bool,
+) -> std::fmt::Result {
+    let post = &thread.post;
+
+    if markdown {
+        let indent = "  ".repeat(depth);
+        writeln!(
+            output,
+            "{}- **{}** | {} | [link]({}{})\n",
+            indent,
+            post_author(post),
+            format_time(post),
+            ITEM_URL,
+            post.id()
+        )?;
+        write_indented(output, &post_text(post, true), &format!("{}  ", indent))?;
+    } else {
+        let indent = "    ".repeat(depth);
+        writeln!(
+            output,
+            "{}{} | {}",
+            indent,
+            post_author(post),
+            format_time(post)
+        )?;
+        write_indented(output, &post_text(post, false), &format!("{}  ", indent))?;
+    }
+
+    for reply in &thread.replies {
+        write_text_thread(output, reply, depth + 1, markdown)?;
+    }
+
+    Ok(())
+}
+
+fn write_indented(output: &mut String, text: &str, indent: &str) -> std::fmt::Result {
+    for line in text.lines() {
+        if line.is_empty() {
+            writeln!(output)?;
+        } else {
+            writeln!(output, "{}{}", indent, line)?;
+        }
+    }
+    writeln!(output)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn post(json: &str) -> Post {
+        serde_json::from_str(json).unwrap()
+    }
+
+    #[test]
+    fn render_html_decodes_entities() {
+        assert_eq!(
+            render_html(
+                "a &amp; b &lt;c&gt; &quot;d&quot; &#x27;e&#39; &#x2F;",
+                false
+            ),
+            "a & b <c> \"d\" 'e' /"
+        );
+        assert_eq!(render_html("&unknown; &#xZZ;", false), "&unknown; &#xZZ;");
+    }
+
+    #[test]
+    fn render_html_splits_paragraphs() {
+        assert_eq!(
+            render_html("One<p>Two<p><i>Three</i>", false),
+            "One\n\nTwo\n\nThree"
+        );
+        assert_eq!(render_html("One<p><i>Two</i>", true), "One\n\n*Two*");
+    }
+
+    #[test]
+    fn render_html_renders_links() {
+        let html = r#"See <a href="https:&#x2F;&#x2F;example.com" rel="nofollow">https:&#x2F;&#x2F;example.com</a>"#;
+        assert_eq!(render_html(html, false), "See https://example.com");
+        assert_eq!(
+            render_html(html, true),
+            "See [https://example.com](https://example.com)"
+        );
+
+        let html = r#"<a href="https://example.com/a">docs</a>"#;
+        assert_eq!(render_html(html, false), "docs (https://example.com/a)");
+        assert_eq!(render_html(html, true), "[docs](https://example.com/a)");
+    }
+
+    #[test]
+    fn render_html_sets_code_apart_with_single_blank_lines() {
+        let html = "Try this:<p><pre><code>  fn main() {}\n  \n</code></pre>\nThen<p>done";
+        assert_eq!(
+            render_html(html, true),
+            "Try this:\n\n```\n  fn main() {}\n```\n\nThen\n\ndone"
+        );
+        assert_eq!(
+            render_html(html, false),
+            "Try this:\n\n  fn main() {}\n\nThen\n\ndone"
+        );
+    }
+
+    #[test]
+    fn csv_field_quotes_only_when_needed() {
+        assert_eq!(csv_field("plain"), "plain");
+        assert_eq!(csv_field("a, b"), "\"a, b\"");
+        assert_eq!(csv_field("say \"hi\""), "\"say \"\"hi\"\"\"");
+        assert_eq!(csv_field("two\nlines"), "\"two\nlines\"");
+    }
+
+    #[test]
+    fn csv_feed_export_quotes_titles() {
+        let items = vec![(
+            1,
+            post(
+                r#"{"type": "story", "id": 1, "by": "pg", "time": 0, "title": "Rust, \"quoted\"", "score": 3}"#,
+            ),
+        )];
+        let csv = export_feed("top", &items, Format::Csv).unwrap();
+        assert_eq!(
+            csv.lines().nth(1),
+            Some(
+                r#"1,1,story,"Rust, ""quoted""",,pg,3,0,0,https://news.ycombinator.com/item?id=1"#
+            )
+        );
+    }
+
+    #[test]
+    fn json_export_round_trips() {
+        let items = vec![
+            (
+                1,
+                post(
+                    r#"{"type": "story", "id": 1, "by": "pg", "time": 1600000000, "title": "A", "url": "https://a.com", "score": 3, "descendants": 1, "kids": [2]}"#,
+                ),
+            ),
+            (
+                2,
+                post(
+                    r#"{"type": "job", "id": 3, "by": "corp", "time": 1600000000, "title": "Hiring", "text": "<p>Join"}"#,
+                ),
+            ),
+        ];
+        let json = export_feed("top", &items, Format::Json).unwrap();
+
+        let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+        for (exported, (rank, item)) in value.as_array().unwrap().iter().zip(&items) {
+            assert_eq!(exported["rank"], *rank);
+            let parsed: Post = serde_json::from_value(exported["item"].clone()).unwrap();
+            assert_eq!(
+                serde_json::to_string(&parsed).unwrap(),
+                serde_json::to_string(item).unwrap()
+            );
+        }
+
+        let thread = Thread {
+            post: items[0].1.clone(),
+            replies: vec![Thread {
+                post: post(
+                    r#"{"type": "comment", "id": 2, "by": "bob", "time": 1600000000, "parent": 1, "text": "Hi"}"#,
+                ),
+                replies: Vec::new(),
+            }],
+        };
+        let json = export_thread(&thread, Format::Json).unwrap();
+        let parsed: Thread = serde_json::from_str(&json).unwrap();
+        assert_eq!(serde_json::to_string_pretty(&parsed).unwrap(), json);
+    }
+
+    #[test]
+    fn markdown_thread_names_missing_authors() {
+        let thread = Thread {
+            post: post(r#"{"type": "story", "id": 1, "by": "pg", "time": 0, "title": "A"}"#),
+            replies: vec![
+                Thread {
+                    post: post(r#"{"type": "comment", "id": 2, "time": 0, "deleted": true}"#),
+                    replies: Vec::new(),
+                },
+                Thread {
+                    post: post(r#"{"type": "comment", "id": 3, "time": 0, "dead": true}"#),
+                    replies: Vec::new(),
+                },
+            ],
+        };
+        let markdown = export_thread(&thread, Format::Markdown).unwrap();
+        assert!(!markdown.contains("****"));
+        assert!(markdown.contains("- **[deleted]** |"));
+        assert!(markdown.contains("- **[dead]** |"));
+    }
+}
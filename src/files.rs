@@ -0,0 +1,83 @@
+use directories::ProjectDirs;
+use serde::de::DeserializeOwned;
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+/// Returns the directories of hntui, which can't be found without a home directory.
+pub fn project_dirs() -> Result<ProjectDirs, io::Error> {
+    ProjectDirs::from("me", "bramw", "hntui").ok_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::NotFound,
+            "no home directory to keep the files of hntui in",
+        )
+    })
+}
+
+/// Returns the path of the file with the name in the data directory, which is created if it
+/// doesn't exist yet.
+pub fn data_file(name: &str) -> Result<PathBuf, io::Error> {
+    let dirs = project_dirs()?;
+    fs::create_dir_all(dirs.data_dir())?;
+    Ok(dirs.data_dir().join(name))
+}
+
+/// Reads the JSON file, or returns the default value if there is no file yet. A file that
+/// can't be parsed is left alone and also gives the default value, along with a message
+/// saying why, and what is used instead, like `starting with no bookmarks`.
+pub fn load_json<T: DeserializeOwned + Default>(
+    path: &Path,
+    instead: &str,
+) -> Result<(T, Option<String>), io::Error> {
+    if !path.exists() {
+        return Ok((T::default(), None));
+    }
+
+    match serde_json::from_str(&fs::read_to_string(path)?) {
+        Ok(value) => Ok((value, None)),
+        Err(err) => Ok((
+            T::default(),
+            Some(format!(
+                "Couldn't read {}, {}: {}",
+                path.display(),
+                instead,
+                err
+            )),
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn load_json_falls_back_to_the_default() {
+        let dir = std::env::temp_dir().join(format!("hntui-files-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("numbers.json");
+
+        let (numbers, error): (Vec<u32>, _) = load_json(&path, "starting empty").unwrap();
+        assert!(numbers.is_empty());
+        assert_eq!(error, None);
+
+        fs::write(&path, "[1, 2]").unwrap();
+        let (numbers, error): (Vec<u32>, _) = load_json(&path, "starting empty").unwrap();
+        assert_eq!(numbers, vec![1, 2]);
+        assert_eq!(error, None);
+
+        fs::write(&path, "[1,").unwrap();
+        let (numbers, error): (Vec<u32>, _) = load_json(&path, "starting empty").unwrap();
+        assert!(numbers.is_empty());
+        let error = error.unwrap();
+        assert!(error.starts_with(&format!(
+            "Couldn't read {}, starting empty: ",
+            path.display()
+        )));
+        // The file is kept, so it can still be fixed by hand.
+        assert_eq!(fs::read_to_string(&path).unwrap(), "[1,");
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,126 @@
+use crate::extract_url;
+use api::Post;
+use regex::Regex;
+use std::collections::HashSet;
+
+#[derive(Debug, Default)]
+pub struct Filters {
+    pub domains: Vec<String>,
+    pub authors: Vec<String>,
+    pub titles: Vec<Regex>,
+}
+
+impl Filters {
+    /// Returns true if the post is muted by any of the domain, author or title rules.
+    pub fn matches(&self, post: &Post) -> bool {
+        let author = post.by();
+        if self
+            .authors
+            .iter()
+            .any(|muted| muted.eq_ignore_ascii_case(author))
+        {
+            return true;
+        }
+
+        if let Some(domain) = extract_url(post.url()) {
+            let domain = domain.to_lowercase();
+            if self
+                .domains
+                .iter()
+                .any(|muted| domain_matches(&domain, muted))
+            {
+                return true;
+            }
+        }
+
+        let title = post.title();
+        !title.is_empty() && self.titles.iter().any(|regex| regex.is_match(title))
+    }
+
+    /// Removes the muted posts from `items`, unless `show_hidden` is set.
+    /// Also returns the ids of the posts matching a rule, whether they were removed or not.
+    pub fn apply(
+        &self,
+        items: Vec<(usize, Post)>,
+        show_hidden: bool,
+    ) -> (Vec<(usize, Post)>, HashSet<u32>) {
+        let mut muted = HashSet::new();
+        let items = items
+            .into_iter()
+            .filter(|(_, post)| {
+                if self.matches(post) {
+                    muted.insert(post.id());
+                    show_hidden
+                } else {
+                    true
+                }
+            })
+            .collect();
+
+        (items, muted)
+    }
+}
+
+/// Returns true if `domain` is `rule` or one of its subdomains.
+pub fn domain_matches(domain: &str, rule: &str) -> bool {
+    domain == rule || domain.ends_with(&format!(".{}", rule))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::story;
+
+    fn filters() -> Filters {
+        Filters {
+            domains: vec![String::from("example.com")],
+            authors: vec![String::from("Spammer")],
+            titles: vec![Regex::new("(?i)crypto").unwrap()],
+        }
+    }
+
+    #[test]
+    fn matches_domains_and_their_subdomains() {
+        assert!(domain_matches("example.com", "example.com"));
+        assert!(domain_matches("blog.example.com", "example.com"));
+        assert!(!domain_matches("notexample.com", "example.com"));
+        assert!(!domain_matches("example.com.au", "example.com"));
+        assert!(!domain_matches("com", "example.com"));
+    }
+
+    #[test]
+    fn matches_posts_by_any_rule() {
+        let filters = filters();
+        let matches =
+            |by, title, url| filters.matches(&story(1).by(by).title(title).url(url).build());
+
+        assert!(matches("a", "Rust", "https://Blog.Example.com/post"));
+        assert!(!matches("a", "Rust", "https://notexample.com/post"));
+        assert!(matches("spammer", "Rust", ""));
+        assert!(matches("a", "Buy Crypto now", ""));
+        assert!(!matches("a", "Rust", ""));
+        let post = story(1).by("spammer").title("Crypto").build();
+        assert!(!Filters::default().matches(&post));
+    }
+
+    #[test]
+    fn apply_removes_or_keeps_the_muted_posts() {
+        let items = vec![
+            (0, story(1).by("a").title("Rust").build()),
+            (1, story(2).by("spammer").title("Rust").build()),
+            (2, story(3).by("a").title("Crypto").build()),
+        ];
+        let muted: HashSet<u32> = [2, 3].iter().copied().collect();
+
+        let (shown, hidden) = filters().apply(items.clone(), false);
+        assert_eq!(
+            shown.iter().map(|(_, post)| post.id()).collect::<Vec<_>>(),
+            vec![1]
+        );
+        assert_eq!(hidden, muted);
+
+        let (shown, hidden) = filters().apply(items, true);
+        assert_eq!(shown.len(), 3);
+        assert_eq!(hidden, muted);
+    }
+}
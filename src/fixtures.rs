@@ -0,0 +1,63 @@
+use api::{Post, Story};
+use time::OffsetDateTime;
+
+/// Builds a story for the tests, with the fields a test doesn't set left empty.
+pub struct StoryBuilder {
+    story: Story,
+}
+
+/// Starts building the story with the id, posted at the unix epoch.
+pub fn story(id: u32) -> StoryBuilder {
+    StoryBuilder {
+        story: Story {
+            id,
+            deleted: false,
+            by: String::new(),
+            time: OffsetDateTime::unix_epoch(),
+            dead: false,
+            kids: Vec::new(),
+            descendants: 0,
+            score: 0,
+            title: String::new(),
+            url: String::new(),
+            text: String::new(),
+        },
+    }
+}
+
+impl StoryBuilder {
+    pub fn by(mut self, by: &str) -> Self {
+        self.story.by = by.to_string();
+        self
+    }
+
+    pub fn title(mut self, title: &str) -> Self {
+        self.story.title = title.to_string();
+        self
+    }
+
+    pub fn url(mut self, url: &str) -> Self {
+        self.story.url = url.to_string();
+        self
+    }
+
+    pub fn score(mut self, score: u32) -> Self {
+        self.story.score = score;
+        self
+    }
+
+    pub fn comments(mut self, comments: u32) -> Self {
+        self.story.descendants = comments;
+        self
+    }
+
+    /// Sets the unix timestamp the story was posted at.
+    pub fn time(mut self, timestamp: i64) -> Self {
+        self.story.time = OffsetDateTime::from_unix_timestamp(timestamp);
+        self
+    }
+
+    pub fn build(self) -> Post {
+        Post::Story(self.story)
+    }
+}
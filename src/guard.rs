@@ -0,0 +1,50 @@
+use crossterm::{
+    cursor::Show,
+    event::{DisableMouseCapture, EnableMouseCapture},
+    execute,
+    terminal::{disable_raw_mode, enable_raw_mode},
+};
+
+/// Puts the terminal in raw mode, capturing the mouse if asked to, and restores it when
+/// dropped, so it is restored whichever way the app exits. A panic restores it too, before
+/// the panic message is printed.
+pub struct TerminalGuard;
+
+impl TerminalGuard {
+    pub fn new(mouse: bool) -> crossterm::Result<Self> {
+        let hook = std::panic::take_hook();
+        std::panic::set_hook(Box::new(move |info| {
+            restore();
+            hook(info);
+        }));
+
+        enable_raw_mode()?;
+        set_mouse_capture(mouse)?;
+
+        Ok(TerminalGuard)
+    }
+}
+
+impl Drop for TerminalGuard {
+    fn drop(&mut self) {
+        restore();
+    }
+}
+
+/// Starts or stops capturing the mouse, like when the config is reloaded.
+pub fn set_mouse_capture(mouse: bool) -> crossterm::Result<()> {
+    if mouse {
+        execute!(std::io::stdout(), EnableMouseCapture)
+    } else {
+        execute!(std::io::stdout(), DisableMouseCapture)
+    }
+}
+
+/// Leaves raw mode and shows the cursor again. Errors are ignored, as there is nothing left to
+/// do about them while exiting. The mouse capture is stopped whether it was asked for at the
+/// start or not, as reloading the config can start it.
+fn restore() {
+    let _ = disable_raw_mode();
+    let _ = execute!(std::io::stdout(), DisableMouseCapture);
+    let _ = execute!(std::io::stdout(), Show);
+}
@@ -0,0 +1,197 @@
+use crate::{
+    config::{join_list, split_list},
+    extract_url,
+    filters::domain_matches,
+    ui::{format_style, parse_style},
+};
+use api::Post;
+use regex::Regex;
+use std::fmt;
+use tui::style::Style;
+
+#[derive(Debug)]
+pub enum Matcher {
+    Title(Regex),
+    Domain(String),
+    Author(String),
+}
+
+#[derive(Debug)]
+pub struct HighlightRule {
+    pub matcher: Matcher,
+    pub style: Style,
+}
+
+impl HighlightRule {
+    /// Parses a rule of the form `title:rust|tokio, yellow, bold`, where the first
+    /// entry selects what to match on and the remaining entries make up the style. A regex
+    /// with a comma is quoted, like `title:"a, b", yellow`.
+    pub fn parse(value: &str) -> Result<Self, String> {
+        let parts = split_list(value)?;
+        let mut parts = parts.iter().map(String::as_str);
+        let rule = parts.next().unwrap_or_default();
+
+        let matcher = match rule.split_once(':') {
+            Some(("title", pattern)) => Matcher::Title(
+                Regex::new(pattern)
+                    .map_err(|err| format!("{} is not a valid regex: {}", pattern, err))?,
+            ),
+            Some(("domain", domain)) => Matcher::Domain(domain.to_lowercase()),
+            Some(("author", author)) => Matcher::Author(author.to_string()),
+            _ => {
+                return Err(format!(
+                    "{} should start with title:, domain: or author:",
+                    rule
+                ))
+            }
+        };
+
+        Ok(HighlightRule {
+            matcher,
+            style: parse_style(parts)?,
+        })
+    }
+
+    pub fn matches(&self, post: &Post) -> bool {
+        match &self.matcher {
+            Matcher::Title(regex) => regex.is_match(post.title()),
+            Matcher::Domain(rule) => match extract_url(post.url()) {
+                Some(domain) => domain_matches(&domain.to_lowercase(), rule),
+                None => false,
+            },
+            Matcher::Author(author) => author.eq_ignore_ascii_case(post.by()),
+        }
+    }
+}
+
+/// Writes the rule in the syntax it's parsed from.
+impl fmt::Display for HighlightRule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let matcher = match &self.matcher {
+            Matcher::Title(regex) => format!("title:{}", regex.as_str()),
+            Matcher::Domain(domain) => format!("domain:{}", domain),
+            Matcher::Author(author) => format!("author:{}", author),
+        };
+        f.write_str(&join_list(&[matcher]))?;
+
+        let style = format_style(self.style);
+        if !style.is_empty() {
+            write!(f, ", {}", style)?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct Highlights {
+    /// The rules with the keys they are set with in the `[highlight]` section.
+    pub rules: Vec<(String, HighlightRule)>,
+}
+
+impl Highlights {
+    /// Returns the combined style of every rule matching the post, in the order the rules were
+    /// defined, or `None` if no rule matches.
+    pub fn style(&self, post: &Post) -> Option<Style> {
+        self.rules
+            .iter()
+            .map(|(_, rule)| rule)
+            .filter(|rule| rule.matches(post))
+            .fold(None, |style: Option<Style>, rule| {
+                Some(style.unwrap_or_default().patch(rule.style))
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::story;
+    use tui::style::{Color, Modifier};
+
+    #[test]
+    fn parses_rules() {
+        let rule = HighlightRule::parse("title:rust|tokio, yellow, bold").unwrap();
+        assert!(matches!(&rule.matcher, Matcher::Title(regex) if regex.as_str() == "rust|tokio"));
+        assert_eq!(
+            rule.style,
+            Style::default()
+                .fg(Color::Yellow)
+                .add_modifier(Modifier::BOLD)
+        );
+
+        let rule = HighlightRule::parse("domain:GitHub.com, bg:blue").unwrap();
+        assert!(matches!(&rule.matcher, Matcher::Domain(domain) if domain == "github.com"));
+        assert_eq!(rule.style, Style::default().bg(Color::Blue));
+
+        let rule = HighlightRule::parse("author:pg").unwrap();
+        assert!(matches!(&rule.matcher, Matcher::Author(author) if author == "pg"));
+        assert_eq!(rule.style, Style::default());
+    }
+
+    #[test]
+    fn parses_a_quoted_regex_with_a_comma() {
+        let rule = HighlightRule::parse(r#""title:rust, tokio", yellow"#).unwrap();
+        assert!(rule.matches(&story(1).title("rust, tokio and more").build()));
+        assert!(!rule.matches(&story(1).title("rust").build()));
+        assert_eq!(rule.to_string(), r#""title:rust, tokio", yellow"#);
+    }
+
+    #[test]
+    fn writes_rules_like_they_are_parsed() {
+        for value in &[
+            "title:rust|tokio, yellow, bold",
+            "domain:github.com, bg:blue",
+            "author:pg",
+        ] {
+            assert_eq!(HighlightRule::parse(value).unwrap().to_string(), *value);
+        }
+    }
+
+    #[test]
+    fn reports_invalid_rules() {
+        let error = |value| HighlightRule::parse(value).unwrap_err();
+        assert_eq!(
+            error("points:100, red"),
+            "points:100 should start with title:, domain: or author:"
+        );
+        assert_eq!(error(""), " should start with title:, domain: or author:");
+        assert!(error("title:(rust, red").starts_with("(rust is not a valid regex: "));
+        assert_eq!(
+            error(r#""title:rust, red"#),
+            r#""title:rust, red has a quote that isn't closed"#
+        );
+        assert_eq!(error("author:pg, shiny"), "shiny is not a valid style");
+    }
+
+    #[test]
+    fn matches_posts_and_combines_styles() {
+        let highlights = Highlights {
+            rules: vec![
+                (
+                    String::from("rust"),
+                    HighlightRule::parse("title:(?i)rust, yellow").unwrap(),
+                ),
+                (
+                    String::from("github"),
+                    HighlightRule::parse("domain:github.com, bold").unwrap(),
+                ),
+            ],
+        };
+
+        let post = story(1).title("Go").url("https://go.dev").build();
+        assert_eq!(highlights.style(&post), None);
+        assert_eq!(
+            highlights.style(
+                &story(1)
+                    .title("Rust")
+                    .url("https://gist.github.com/a")
+                    .build()
+            ),
+            Some(
+                Style::default()
+                    .fg(Color::Yellow)
+                    .add_modifier(Modifier::BOLD)
+            )
+        );
+    }
+}
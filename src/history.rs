@@ -0,0 +1,175 @@
+use crate::files;
+use api::Post;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{HashMap, HashSet},
+    path::PathBuf,
+};
+use time::OffsetDateTime;
+
+/// Items that were seen in a list, or opened, longer ago than this are forgotten.
+const RETENTION_SECONDS: i64 = 30 * 24 * 60 * 60;
+
+/// A comment thread that was opened, with its comment count and the unix timestamp at that time.
+#[derive(Debug, Serialize, Deserialize)]
+struct OpenedThread {
+    comments: u32,
+    opened_at: i64,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct History {
+    #[serde(skip)]
+    path: PathBuf,
+    /// Why the history file couldn't be read, if it couldn't.
+    #[serde(skip)]
+    pub read_error: Option<String>,
+    /// Items that were seen in the list since the last refresh and hadn't been seen before.
+    #[serde(skip)]
+    new: HashSet<u32>,
+    /// Ids of the items seen in a list, with the unix timestamp they were last seen at.
+    #[serde(default)]
+    seen: HashMap<u32, i64>,
+    /// Ids of the items whose article was opened, with the unix timestamp it was last opened at.
+    #[serde(default)]
+    opened_articles: HashMap<u32, i64>,
+    /// Ids of the items whose comment thread was opened.
+    #[serde(default)]
+    opened_threads: HashMap<u32, OpenedThread>,
+    /// The opened articles and threads as written by older versions, without timestamps. They
+    /// are moved to the fields above when the history is read.
+    #[serde(default, skip_serializing)]
+    articles: HashSet<u32>,
+    #[serde(default, skip_serializing)]
+    threads: HashMap<u32, u32>,
+}
+
+impl History {
+    pub fn new() -> Result<Self, std::io::Error> {
+        let path = files::data_file("history.json")?;
+        let (mut history, read_error): (History, _) =
+            files::load_json(&path, "starting with an empty history")?;
+
+        history.forget_old(OffsetDateTime::now_utc().unix_timestamp());
+        history.path = path;
+        history.read_error = read_error;
+
+        Ok(history)
+    }
+
+    /// Takes over the history of older versions as if it was from `now`, and forgets the
+    /// items that were seen or opened longer ago than the retention.
+    fn forget_old(&mut self, now: i64) {
+        for id in std::mem::take(&mut self.articles) {
+            self.opened_articles.entry(id).or_insert(now);
+        }
+        for (id, comments) in std::mem::take(&mut self.threads) {
+            self.opened_threads.entry(id).or_insert(OpenedThread {
+                comments,
+                opened_at: now,
+            });
+        }
+
+        let kept = |at: i64| now - at < RETENTION_SECONDS;
+        self.seen.retain(|_, seen_at| kept(*seen_at));
+        self.opened_articles.retain(|_, opened_at| kept(*opened_at));
+        self.opened_threads
+            .retain(|_, thread| kept(thread.opened_at));
+    }
+
+    pub fn write(&self) -> Result<(), std::io::Error> {
+        std::fs::write(&self.path, serde_json::to_string(self)?)
+    }
+
+    /// Records the items as seen, marking the ones that weren't seen before as new.
+    /// When `refreshed` is set the previous new markers are cleared first.
+    pub fn see(&mut self, items: &[(usize, Post)], refreshed: bool) {
+        if refreshed {
+            self.new.clear();
+        }
+
+        let now = OffsetDateTime::now_utc().unix_timestamp();
+        for (_, post) in items {
+            if self.seen.insert(post.id(), now).is_none() {
+                self.new.insert(post.id());
+            }
+        }
+    }
+
+    pub fn open_article(&mut self, id: u32) {
+        let now = OffsetDateTime::now_utc().unix_timestamp();
+        self.opened_articles.insert(id, now);
+    }
+
+    pub fn open_thread(&mut self, id: u32, descendants: u32) {
+        let thread = OpenedThread {
+            comments: descendants,
+            opened_at: OffsetDateTime::now_utc().unix_timestamp(),
+        };
+        self.opened_threads.insert(id, thread);
+    }
+
+    pub fn is_new(&self, id: u32) -> bool {
+        self.new.contains(&id)
+    }
+
+    pub fn is_visited(&self, id: u32) -> bool {
+        self.opened_articles.contains_key(&id) || self.opened_threads.contains_key(&id)
+    }
+
+    /// Returns the number of comments added since the thread was last opened,
+    /// or `None` if it was never opened.
+    pub fn new_comments(&self, id: u32, descendants: u32) -> Option<u32> {
+        self.opened_threads
+            .get(&id)
+            .map(|thread| descendants.saturating_sub(thread.comments))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DAY: i64 = 24 * 60 * 60;
+
+    #[test]
+    fn forgets_items_after_the_retention() {
+        let now = 100 * DAY;
+        let mut history: History = serde_json::from_value(serde_json::json!({
+            "seen": { "1": now - DAY, "2": now - 31 * DAY },
+            "opened_articles": { "3": now - DAY, "4": now - 31 * DAY },
+            "opened_threads": {
+                "5": { "comments": 2, "opened_at": now - DAY },
+                "6": { "comments": 2, "opened_at": now - 31 * DAY },
+            },
+        }))
+        .unwrap();
+        history.forget_old(now);
+
+        let mut seen: Vec<_> = history.seen.keys().copied().collect();
+        seen.sort_unstable();
+        assert_eq!(seen, [1]);
+        assert!(history.is_visited(3));
+        assert!(!history.is_visited(4));
+        assert_eq!(history.new_comments(5, 7), Some(5));
+        assert_eq!(history.new_comments(6, 7), None);
+    }
+
+    #[test]
+    fn takes_over_the_history_without_timestamps() {
+        let mut history: History = serde_json::from_value(serde_json::json!({
+            "articles": [1],
+            "threads": { "2": 3 },
+        }))
+        .unwrap();
+        history.forget_old(100 * DAY);
+
+        assert!(history.is_visited(1));
+        assert_eq!(history.new_comments(2, 5), Some(2));
+        let written = serde_json::to_value(&history).unwrap();
+        assert_eq!(written["opened_articles"]["1"], 100 * DAY);
+        assert_eq!(written["opened_threads"]["2"]["comments"], 3);
+        assert!(written.get("articles").is_none());
+        assert!(written.get("threads").is_none());
+    }
+}
@@ -1,69 +1,121 @@
+use crate::ui::MenuItem;
 use api::Post;
 use futures::{stream, StreamExt};
-use tui::widgets::ListItem;
+use std::{cmp::Ordering, collections::HashMap, fmt};
+use time::OffsetDateTime;
 
-pub struct TopItems {
-    vec: Vec<(usize, Post)>,
-    pub list: Vec<ListItem<'static>>,
-    client: api::Client,
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum SortMode {
+    Rank,
+    Score,
+    Comments,
+    Age,
+    ScorePerHour,
 }
 
-impl TopItems {
-    pub fn new(client: api::Client) -> TopItems {
-        TopItems {
-            vec: Vec::new(),
-            list: Vec::new(),
-            client,
-        }
+impl fmt::Display for SortMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            SortMode::Rank => "rank",
+            SortMode::Score => "score",
+            SortMode::Comments => "comments",
+            SortMode::Age => "age",
+            SortMode::ScorePerHour => "score/hour",
+        })
     }
+}
 
-    pub async fn get_vec(
-        &mut self,
-        item_length: u16,
-    ) -> Result<Vec<(usize, Post)>, Box<dyn std::error::Error>> {
-        if self.vec.is_empty() {
-            Self::set_vec(self, item_length).await?;
-        }
-
-        Ok(self.vec.clone())
+impl SortMode {
+    pub fn cycle(&mut self) {
+        *self = match *self {
+            SortMode::Rank => SortMode::Score,
+            SortMode::Score => SortMode::Comments,
+            SortMode::Comments => SortMode::Age,
+            SortMode::Age => SortMode::ScorePerHour,
+            SortMode::ScorePerHour => SortMode::Rank,
+        };
     }
 
-    pub async fn refresh(&mut self, item_length: u16) -> Result<(), Box<dyn std::error::Error>> {
-        Self::set_vec(self, item_length).await?;
-        Ok(())
-    }
+    /// Sorts the items in place, keeping their original rank. Ties are ordered by rank.
+    pub fn sort(self, items: &mut [(usize, Post)], current_time: OffsetDateTime) {
+        let score_per_hour = |post: &Post| {
+            let hours = (current_time - post.time()).whole_seconds().max(60) as f64 / 3600.0;
+            post.score() as f64 / hours
+        };
 
-    async fn set_vec(&mut self, item_length: u16) -> Result<(), Box<dyn std::error::Error>> {
-        self.vec = Self::get_items(self.client.clone(), item_length).await?;
-        Ok(())
+        items.sort_by(|(a_pos, a), (b_pos, b)| {
+            let ordering = match self {
+                SortMode::Rank => Ordering::Equal,
+                SortMode::Score => b.score().cmp(&a.score()),
+                SortMode::Comments => b.descendants().cmp(&a.descendants()),
+                SortMode::Age => b.time().cmp(&a.time()),
+                SortMode::ScorePerHour => score_per_hour(b)
+                    .partial_cmp(&score_per_hour(a))
+                    .unwrap_or(Ordering::Equal),
+            };
+
+            ordering.then(a_pos.cmp(b_pos))
+        });
     }
+}
 
-    async fn get_items(
-        client: api::Client,
-        item_length: u16,
-    ) -> Result<Vec<(usize, Post)>, Box<dyn std::error::Error>> {
-        let stories = client
-            .get_top_stories(&format!("orderBy=\"$key\"&limitToFirst={}", item_length))
-            .await?;
+/// How a post changed in the last refresh of its feed.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Change {
+    /// The post wasn't in the feed before the refresh.
+    New,
+    /// The number of places the post rose, negative if it fell, and how much its score and
+    /// comment count grew.
+    Moved {
+        rank: i64,
+        score: i64,
+        comments: i64,
+    },
+}
 
-        let items = construct_items(stories, client).await?;
+/// Compares the items of a feed before and after a refresh, by the id of every item after it.
+pub fn changes(previous: &[(usize, Post)], items: &[(usize, Post)]) -> HashMap<u32, Change> {
+    let previous: HashMap<u32, (usize, &Post)> = previous
+        .iter()
+        .map(|(pos, post)| (post.id(), (*pos, post)))
+        .collect();
 
-        Ok(items)
-    }
+    items
+        .iter()
+        .map(|(pos, post)| {
+            let change = match previous.get(&post.id()) {
+                Some((previous_pos, previous_post)) => Change::Moved {
+                    rank: *previous_pos as i64 - *pos as i64,
+                    score: i64::from(post.score()) - i64::from(previous_post.score()),
+                    comments: i64::from(post.descendants())
+                        - i64::from(previous_post.descendants()),
+                },
+                None => Change::New,
+            };
+            (post.id(), change)
+        })
+        .collect()
 }
 
-pub struct NewItems {
+/// The posts of a feed fetched from the API, kept until the feed is refreshed.
+pub struct FeedItems {
+    feed: MenuItem,
     vec: Vec<(usize, Post)>,
-    pub list: Vec<ListItem<'static>>,
     client: api::Client,
+    /// When the items were last fetched.
+    pub fetched: Option<OffsetDateTime>,
+    /// How the items changed in the last refresh.
+    pub changes: HashMap<u32, Change>,
 }
 
-impl NewItems {
-    pub fn new(client: api::Client) -> NewItems {
-        NewItems {
+impl FeedItems {
+    pub fn new(client: api::Client, feed: MenuItem) -> FeedItems {
+        FeedItems {
+            feed,
             vec: Vec::new(),
-            list: Vec::new(),
             client,
+            fetched: None,
+            changes: HashMap::new(),
         }
     }
 
@@ -78,23 +130,46 @@ impl NewItems {
         Ok(self.vec.clone())
     }
 
+    /// Forgets the fetched items, so the next `get_vec` fetches them again.
+    pub fn clear(&mut self) {
+        self.vec.clear();
+        self.fetched = None;
+        self.changes.clear();
+    }
+
     pub async fn refresh(&mut self, item_length: u16) -> Result<(), Box<dyn std::error::Error>> {
+        let previous = self.vec.clone();
         Self::set_vec(self, item_length).await?;
+        // There is nothing to compare with if the items were never fetched.
+        self.changes = if previous.is_empty() {
+            HashMap::new()
+        } else {
+            changes(&previous, &self.vec)
+        };
         Ok(())
     }
 
     async fn set_vec(&mut self, item_length: u16) -> Result<(), Box<dyn std::error::Error>> {
-        self.vec = Self::get_items(self.client.clone(), item_length).await?;
+        self.vec = Self::get_items(self.client.clone(), self.feed, item_length).await?;
+        self.fetched = Some(OffsetDateTime::now_utc());
         Ok(())
     }
 
     async fn get_items(
         client: api::Client,
+        feed: MenuItem,
         item_length: u16,
     ) -> Result<Vec<(usize, Post)>, Box<dyn std::error::Error>> {
-        let stories = client
-            .get_new_stories(&format!("orderBy=\"$key\"&limitToFirst={}", item_length))
-            .await?;
+        let options = format!("orderBy=\"$key\"&limitToFirst={}", item_length);
+        let stories = match feed {
+            MenuItem::Top => client.get_top_stories(&options).await?,
+            MenuItem::New => client.get_new_stories(&options).await?,
+            MenuItem::Ask => client.get_ask_stories(&options).await?,
+            MenuItem::Show => client.get_show_stories(&options).await?,
+            MenuItem::Jobs => client.get_job_stories(&options).await?,
+            // Saved posts come from the bookmarks instead.
+            MenuItem::Saved => Vec::new(),
+        };
 
         let items = construct_items(stories, client).await?;
 
@@ -102,7 +177,7 @@ impl NewItems {
     }
 }
 
-async fn construct_items(
+pub async fn construct_items(
     stories: Vec<u32>,
     client: api::Client,
 ) -> Result<Vec<(usize, Post)>, Box<dyn std::error::Error>> {
@@ -113,26 +188,149 @@ async fn construct_items(
         .map(|(pos, id)| {
             let client = &client;
             async move {
-                let post = client.get_item_by_id(id, "").await.unwrap();
-                (pos + 1, post)
+                client
+                    .get_item_by_id(id, "")
+                    .await
+                    .map(|post| (pos + 1, post))
+                    .map_err(|err| format!("item {}: {}", id, err))
             }
         })
         .buffer_unordered(stories.len());
 
-    #[cfg(debug_assertions)]
-    let a = std::time::Instant::now();
+    let start = std::time::Instant::now();
 
+    let mut error = None;
     requests
-        .fold(&mut items, |items, (pos, post)| async move {
-            items.push((pos, post));
-            items
-        })
+        .fold(
+            (&mut items, &mut error),
+            |(items, error), item| async move {
+                match item {
+                    Ok(item) => items.push(item),
+                    Err(err) => *error = Some(err),
+                }
+                (items, error)
+            },
+        )
         .await;
 
-    #[cfg(debug_assertions)]
-    eprintln!("Total time: {:?}", a.elapsed());
+    tracing::debug!(
+        items = stories.len(),
+        elapsed_ms = start.elapsed().as_millis() as u64,
+        failed = error.is_some(),
+        "fetched items"
+    );
+
+    if let Some(err) = error {
+        return Err(err.into());
+    }
 
     items.sort_by_key(|key| key.0);
 
     Ok(items)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::story;
+
+    const NOW: i64 = 1_700_000_000;
+
+    fn hours_ago(hours: i64) -> i64 {
+        NOW - hours * 3600
+    }
+
+    fn sorted(mode: SortMode, mut items: Vec<(usize, Post)>) -> Vec<u32> {
+        mode.sort(&mut items, OffsetDateTime::from_unix_timestamp(NOW));
+        items.iter().map(|(_, post)| post.id()).collect()
+    }
+
+    fn items() -> Vec<(usize, Post)> {
+        vec![
+            (
+                2,
+                story(30).score(10).comments(5).time(hours_ago(1)).build(),
+            ),
+            (
+                0,
+                story(10).score(50).comments(5).time(hours_ago(10)).build(),
+            ),
+            (
+                1,
+                story(20).score(10).comments(8).time(hours_ago(2)).build(),
+            ),
+            (
+                3,
+                story(40).score(50).comments(1).time(hours_ago(1)).build(),
+            ),
+        ]
+    }
+
+    #[test]
+    fn sorts_by_the_mode_with_ties_in_rank_order() {
+        assert_eq!(sorted(SortMode::Rank, items()), vec![10, 20, 30, 40]);
+        assert_eq!(sorted(SortMode::Score, items()), vec![10, 40, 20, 30]);
+        assert_eq!(sorted(SortMode::Comments, items()), vec![20, 10, 30, 40]);
+        assert_eq!(sorted(SortMode::Age, items()), vec![30, 40, 20, 10]);
+        assert_eq!(
+            sorted(SortMode::ScorePerHour, items()),
+            vec![40, 30, 10, 20]
+        );
+    }
+
+    #[test]
+    fn cycles_through_every_mode() {
+        let mut mode = SortMode::Rank;
+        let mut names = Vec::new();
+        for _ in 0..5 {
+            mode.cycle();
+            names.push(mode.to_string());
+        }
+        assert_eq!(names, ["score", "comments", "age", "score/hour", "rank"]);
+    }
+
+    #[test]
+    fn finds_new_and_moved_posts() {
+        let previous = vec![
+            (
+                0,
+                story(10).score(50).comments(5).time(hours_ago(1)).build(),
+            ),
+            (
+                1,
+                story(20).score(10).comments(8).time(hours_ago(1)).build(),
+            ),
+        ];
+        let items = vec![
+            (
+                0,
+                story(20).score(25).comments(9).time(hours_ago(1)).build(),
+            ),
+            (1, story(30).score(1).comments(0).time(hours_ago(1)).build()),
+            (
+                2,
+                story(10).score(50).comments(5).time(hours_ago(1)).build(),
+            ),
+        ];
+        let changes = changes(&previous, &items);
+
+        assert_eq!(changes.len(), 3);
+        assert_eq!(
+            changes[&20],
+            Change::Moved {
+                rank: 1,
+                score: 15,
+                comments: 1
+            }
+        );
+        assert_eq!(changes[&30], Change::New);
+        assert_eq!(
+            changes[&10],
+            Change::Moved {
+                rank: -2,
+                score: 0,
+                comments: 0
+            }
+        );
+    }
+}
@@ -0,0 +1,320 @@
+use crate::config::split_list;
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use std::fmt;
+
+/// The keys bound to an action. Each binding is a sequence of one or more key presses,
+/// like `ctrl+r` or `g g`.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Keybinding {
+    pub sequences: Vec<Vec<KeyEvent>>,
+}
+
+impl Keybinding {
+    /// Parses a list of bindings separated by commas, where the keys of a sequence are
+    /// separated by spaces and modifiers are prefixed with `+`, like `ctrl+r, alt+enter, g g`.
+    /// The comma and quote keys are called `comma` and `quote`.
+    pub fn parse(value: &str) -> Result<Self, String> {
+        let sequences = split_list(value)?
+            .iter()
+            .map(|sequence| sequence.split_whitespace().map(parse_key).collect())
+            .collect::<Result<_, _>>()?;
+
+        Ok(Keybinding { sequences })
+    }
+
+    /// Returns true if the keys are exactly one of the sequences.
+    pub fn matches(&self, keys: &[KeyEvent]) -> bool {
+        self.sequences.iter().any(|sequence| sequence == keys)
+    }
+
+    /// Returns true if the keys are the start of a longer sequence.
+    pub fn starts_with(&self, keys: &[KeyEvent]) -> bool {
+        self.sequences
+            .iter()
+            .any(|sequence| sequence.len() > keys.len() && sequence.starts_with(keys))
+    }
+}
+
+impl fmt::Display for Keybinding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (index, sequence) in self.sequences.iter().enumerate() {
+            if index > 0 {
+                write!(f, ", ")?;
+            }
+            write_sequence(f, sequence)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Writes the keys in the same syntax they are parsed from.
+pub fn write_sequence(f: &mut impl fmt::Write, keys: &[KeyEvent]) -> fmt::Result {
+    for (index, key) in keys.iter().enumerate() {
+        if index > 0 {
+            write!(f, " ")?;
+        }
+
+        for (modifier, name) in [
+            (KeyModifiers::CONTROL, "ctrl"),
+            (KeyModifiers::ALT, "alt"),
+            (KeyModifiers::SHIFT, "shift"),
+        ] {
+            if key.modifiers.contains(modifier) {
+                write!(f, "{}+", name)?;
+            }
+        }
+
+        match key.code {
+            KeyCode::Char(' ') => write!(f, "space")?,
+            KeyCode::Char(',') => write!(f, "comma")?,
+            KeyCode::Char('"') => write!(f, "quote")?,
+            KeyCode::Char(char) => write!(f, "{}", char)?,
+            KeyCode::F(number) => write!(f, "f{}", number)?,
+            code => write!(
+                f,
+                "{}",
+                KEY_NAMES
+                    .iter()
+                    .find(|(_, named)| *named == code)
+                    .map(|(name, _)| *name)
+                    .unwrap_or("?")
+            )?,
+        }
+    }
+
+    Ok(())
+}
+
+const KEY_NAMES: &[(&str, KeyCode)] = &[
+    ("backspace", KeyCode::Backspace),
+    ("enter", KeyCode::Enter),
+    ("arrow_left", KeyCode::Left),
+    ("arrow_right", KeyCode::Right),
+    ("arrow_up", KeyCode::Up),
+    ("arrow_down", KeyCode::Down),
+    ("home", KeyCode::Home),
+    ("end", KeyCode::End),
+    ("page_up", KeyCode::PageUp),
+    ("page_down", KeyCode::PageDown),
+    ("tab", KeyCode::Tab),
+    ("back_tab", KeyCode::BackTab),
+    ("delete", KeyCode::Delete),
+    ("insert", KeyCode::Insert),
+    ("esc", KeyCode::Esc),
+    ("space", KeyCode::Char(' ')),
+    ("comma", KeyCode::Char(',')),
+    ("quote", KeyCode::Char('"')),
+];
+
+/// Parses a single key with optional modifiers, like `j`, `ctrl+r` or `alt+enter`.
+fn parse_key(key: &str) -> Result<KeyEvent, String> {
+    let mut modifiers = KeyModifiers::NONE;
+    let mut rest = key;
+    while let Some((modifier, tail)) = rest.split_once('+') {
+        if modifier.is_empty() || tail.is_empty() {
+            break;
+        }
+
+        modifiers |= match modifier {
+            "ctrl" => KeyModifiers::CONTROL,
+            "alt" => KeyModifiers::ALT,
+            "shift" => KeyModifiers::SHIFT,
+            _ => return Err(format!("{} is not a valid modifier in {}", modifier, key)),
+        };
+        rest = tail;
+    }
+
+    let code = match KEY_NAMES.iter().find(|(name, _)| *name == rest) {
+        Some((_, code)) => *code,
+        None => {
+            let mut chars = rest.chars();
+            match (chars.next(), chars.next()) {
+                (Some(char), None) => KeyCode::Char(char),
+                (Some('f'), Some(_)) => rest[1..]
+                    .parse::<u8>()
+                    .map(KeyCode::F)
+                    .map_err(|err| format!("{}: {} is not a valid F key", err, rest))?,
+                _ => return Err(format!("{} is not a valid shortcut", key)),
+            }
+        }
+    };
+
+    Ok(normalize(KeyEvent::new(code, modifiers)))
+}
+
+/// Makes key presses comparable to the parsed bindings. Terminals differ in whether they report
+/// shift for upper case characters, so `shift+j` is stored as `J`.
+pub fn normalize(mut key: KeyEvent) -> KeyEvent {
+    match key.code {
+        KeyCode::Char(char) if key.modifiers.contains(KeyModifiers::SHIFT) => {
+            key.code = KeyCode::Char(char.to_uppercase().next().unwrap_or(char));
+            key.modifiers.remove(KeyModifiers::SHIFT);
+        }
+        KeyCode::BackTab => key.modifiers.remove(KeyModifiers::SHIFT),
+        _ => {}
+    }
+
+    key
+}
+
+/// The keys pressed so far of a sequence that isn't complete yet.
+#[derive(Debug, Default)]
+pub struct PendingKeys {
+    pub keys: Vec<KeyEvent>,
+    /// The number typed before the sequence, like the 5 in `5j`.
+    pub count: Option<usize>,
+}
+
+impl PendingKeys {
+    /// Adds a key press and returns the completed sequence if it matches one of the bindings.
+    /// Returns `None` while the keys are the start of a longer sequence or a count is typed.
+    /// Keys that can't start any sequence are dropped from the front until they do, and the
+    /// count is dropped with them if nothing is left. Take the count after a completed sequence.
+    pub fn push<'a, I>(&mut self, key: KeyEvent, bindings: I) -> Option<Vec<KeyEvent>>
+    where
+        I: Iterator<Item = &'a Keybinding> + Clone,
+    {
+        let key = normalize(key);
+
+        // Digits that aren't bound themselves make up the count, but a count can't start with 0.
+        if let KeyCode::Char(digit @ '0'..='9') = key.code {
+            if self.keys.is_empty()
+                && key.modifiers.is_empty()
+                && (digit != '0' || self.count.is_some())
+                && !bindings
+                    .clone()
+                    .any(|binding| binding.matches(&[key]) || binding.starts_with(&[key]))
+            {
+                let digit = digit as usize - '0' as usize;
+                self.count = Some(
+                    self.count
+                        .unwrap_or(0)
+                        .saturating_mul(10)
+                        .saturating_add(digit),
+                );
+                return None;
+            }
+        }
+
+        self.keys.push(key);
+
+        while !self.keys.is_empty() {
+            if bindings.clone().any(|binding| binding.matches(&self.keys)) {
+                return Some(std::mem::take(&mut self.keys));
+            }
+            if bindings
+                .clone()
+                .any(|binding| binding.starts_with(&self.keys))
+            {
+                return None;
+            }
+            self.keys.remove(0);
+        }
+
+        self.count = None;
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(code: KeyCode, modifiers: KeyModifiers) -> KeyEvent {
+        KeyEvent::new(code, modifiers)
+    }
+
+    fn press(char: char) -> KeyEvent {
+        key(KeyCode::Char(char), KeyModifiers::NONE)
+    }
+
+    #[test]
+    fn parses_sequences_and_modifiers() {
+        let binding = Keybinding::parse("ctrl+r, alt+enter, g g,f5").unwrap();
+        assert_eq!(
+            binding.sequences,
+            vec![
+                vec![key(KeyCode::Char('r'), KeyModifiers::CONTROL)],
+                vec![key(KeyCode::Enter, KeyModifiers::ALT)],
+                vec![press('g'), press('g')],
+                vec![key(KeyCode::F(5), KeyModifiers::NONE)],
+            ]
+        );
+        assert_eq!(binding.to_string(), "ctrl+r, alt+enter, g g, f5");
+    }
+
+    #[test]
+    fn parses_the_keys_that_separate_the_list() {
+        let binding = Keybinding::parse("comma, quote, space, \",\"").unwrap();
+        assert_eq!(
+            binding.sequences,
+            vec![
+                vec![press(',')],
+                vec![press('"')],
+                vec![press(' ')],
+                vec![press(',')]
+            ]
+        );
+        assert_eq!(binding.to_string(), "comma, quote, space, comma");
+    }
+
+    #[test]
+    fn rejects_invalid_keys() {
+        assert!(Keybinding::parse("hyper+j").is_err());
+        assert!(Keybinding::parse("jj").is_err());
+        assert!(Keybinding::parse("f99999").is_err());
+        assert_eq!(Keybinding::parse("").unwrap(), Keybinding::default());
+    }
+
+    #[test]
+    fn normalizes_shift() {
+        assert_eq!(
+            normalize(key(KeyCode::Char('j'), KeyModifiers::SHIFT)),
+            press('J')
+        );
+        assert_eq!(
+            normalize(key(KeyCode::Char('J'), KeyModifiers::SHIFT)),
+            press('J')
+        );
+        assert_eq!(
+            normalize(key(KeyCode::BackTab, KeyModifiers::SHIFT)),
+            key(KeyCode::BackTab, KeyModifiers::NONE)
+        );
+        assert_eq!(
+            normalize(key(KeyCode::Char('r'), KeyModifiers::CONTROL)),
+            key(KeyCode::Char('r'), KeyModifiers::CONTROL)
+        );
+        assert_eq!(
+            Keybinding::parse("shift+j").unwrap().sequences,
+            vec![vec![press('J')]]
+        );
+    }
+
+    #[test]
+    fn completes_sequences_with_a_count() {
+        let bindings = [
+            Keybinding::parse("g g").unwrap(),
+            Keybinding::parse("j").unwrap(),
+        ];
+        let mut pending = PendingKeys::default();
+
+        assert_eq!(pending.push(press('1'), bindings.iter()), None);
+        assert_eq!(pending.push(press('2'), bindings.iter()), None);
+        assert_eq!(pending.push(press('g'), bindings.iter()), None);
+        assert_eq!(
+            pending.push(press('g'), bindings.iter()),
+            Some(vec![press('g'), press('g')])
+        );
+        assert_eq!(pending.count.take(), Some(12));
+
+        // A key that can't continue the sequence starts over.
+        assert_eq!(pending.push(press('g'), bindings.iter()), None);
+        assert_eq!(
+            pending.push(press('j'), bindings.iter()),
+            Some(vec![press('j')])
+        );
+        assert_eq!(pending.push(press('x'), bindings.iter()), None);
+        assert!(pending.keys.is_empty());
+    }
+}
@@ -0,0 +1,165 @@
+use crate::files;
+use std::{
+    fs::{self, File, OpenOptions},
+    io::{self, Write},
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex, PoisonError, RwLock,
+    },
+};
+use tracing::{level_filters::LevelFilter, subscriber::Interest, Metadata, Subscriber};
+use tracing_subscriber::layer::{Context, Layer, SubscriberExt};
+
+/// The log file is rotated once it would grow past this size.
+const MAX_SIZE: u64 = 1024 * 1024;
+/// The number of rotated log files kept next to the current one, as `hntui.log.1` and up.
+const ROTATED_FILES: usize = 3;
+
+/// The level of the events that are logged, which changes when the config is reloaded.
+static LEVEL: RwLock<LevelFilter> = RwLock::new(LevelFilter::OFF);
+/// Whether the log file was opened, which only happens once the level isn't `off`.
+static OPENED: AtomicBool = AtomicBool::new(false);
+
+/// Writes the events at `level` and above to `hntui.log` in the data directory, and returns
+/// its path. Nothing is logged if the level is `off`. The TUI owns stdout and stderr, so the
+/// log never goes there.
+///
+/// Calling it again changes the level, like after the config was reloaded.
+pub fn init(level: LevelFilter) -> Result<Option<PathBuf>, io::Error> {
+    *LEVEL.write().unwrap_or_else(PoisonError::into_inner) = level;
+    if level == LevelFilter::OFF {
+        return Ok(None);
+    }
+
+    let path = log_path()?;
+    if OPENED.load(Ordering::SeqCst) {
+        return Ok(Some(path));
+    }
+
+    let file = Arc::new(Mutex::new(RotatingFile::open(path.clone())?));
+    // The level is filtered by `OwnEvents`, so it can change.
+    let subscriber = tracing_subscriber::fmt()
+        .with_max_level(LevelFilter::TRACE)
+        .with_writer(move || LogWriter(file.clone()))
+        .finish()
+        .with(OwnEvents);
+    tracing::subscriber::set_global_default(subscriber)
+        .map_err(|err| io::Error::other(err.to_string()))?;
+    OPENED.store(true, Ordering::SeqCst);
+
+    Ok(Some(path))
+}
+
+pub fn log_path() -> Result<PathBuf, io::Error> {
+    files::data_file("hntui.log")
+}
+
+/// Leaves out the events of dependencies like hyper, which log every connection at the debug
+/// level, so the log only has the requests, loads and actions of hntui itself. Its own events
+/// are left out below the level.
+struct OwnEvents;
+
+impl OwnEvents {
+    fn is_own(metadata: &Metadata<'_>) -> bool {
+        let target = metadata.target();
+        ["hntui", "api"]
+            .iter()
+            .any(|own| target == *own || target.starts_with(&format!("{}::", own)))
+    }
+}
+
+impl<S: Subscriber> Layer<S> for OwnEvents {
+    fn register_callsite(&self, metadata: &'static Metadata<'static>) -> Interest {
+        // The level can change, so whether an event of hntui is logged isn't cached.
+        if Self::is_own(metadata) {
+            Interest::sometimes()
+        } else {
+            Interest::never()
+        }
+    }
+
+    fn enabled(&self, metadata: &Metadata<'_>, _: Context<'_, S>) -> bool {
+        Self::is_own(metadata)
+            && *metadata.level() <= *LEVEL.read().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+/// Returns the path of the rotated log file with the index, like `hntui.log.2`.
+fn rotated_path(path: &Path, index: usize) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(format!(".{}", index));
+    PathBuf::from(name)
+}
+
+struct RotatingFile {
+    path: PathBuf,
+    file: File,
+    size: u64,
+}
+
+impl RotatingFile {
+    fn open(path: PathBuf) -> Result<Self, io::Error> {
+        let file = OpenOptions::new().create(true).append(true).open(&path)?;
+        let size = file.metadata()?.len();
+        Ok(RotatingFile { path, file, size })
+    }
+
+    /// Moves `hntui.log` to `hntui.log.1`, `hntui.log.1` to `hntui.log.2` and so on, replacing
+    /// the oldest file, and starts a new `hntui.log`.
+    fn rotate(&mut self) -> Result<(), io::Error> {
+        for index in (1..ROTATED_FILES).rev() {
+            let from = rotated_path(&self.path, index);
+            if from.exists() {
+                fs::rename(&from, rotated_path(&self.path, index + 1))?;
+            }
+        }
+        fs::rename(&self.path, rotated_path(&self.path, 1))?;
+
+        *self = RotatingFile::open(self.path.clone())?;
+        Ok(())
+    }
+}
+
+impl Write for RotatingFile {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if self.size > 0 && self.size + buf.len() as u64 > MAX_SIZE {
+            self.rotate()?;
+        }
+
+        let written = self.file.write(buf)?;
+        self.size += written as u64;
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.file.flush()
+    }
+}
+
+/// A handle to the log file shared by every thread. A writer is made for every event, which
+/// is written with a single call, so events are never interleaved or split over two files.
+struct LogWriter(Arc<Mutex<RotatingFile>>);
+
+impl Write for LogWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .write(buf)
+    }
+
+    fn write_all(&mut self, buf: &[u8]) -> io::Result<()> {
+        self.0
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .write_all(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.0
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .flush()
+    }
+}
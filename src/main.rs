@@ -1,5 +1,25 @@
+mod actions;
+mod app;
+mod bookmarks;
+mod cli;
 mod config;
+mod draw;
+mod export;
+mod files;
+mod filters;
+#[cfg(test)]
+mod fixtures;
+mod guard;
+mod highlight;
+mod history;
 mod items;
+mod keys;
+mod logging;
+mod notifications;
+mod pane;
+mod session;
+mod template;
+mod theme;
 mod ui;
 
 /*
@@ -7,32 +27,27 @@ mod ui;
  * // TODO: Display note to user when items are being collected (requested) for the New view or Top view
  */
 
-use api::Post;
-use crossterm::{
-    event, event::Event as CEvent, terminal::disable_raw_mode, terminal::enable_raw_mode,
-};
-use futures::executor::block_on;
+use crossterm::{event, event::Event as CEvent, event::MouseEvent};
 use lazy_static::lazy_static;
 use regex::Regex;
 use std::str;
 use std::{sync::mpsc, thread, time::Duration, time::Instant};
 use time::OffsetDateTime;
 
+use app::App;
+use bookmarks::Bookmarks;
 use config::Config;
-use items::{NewItems, TopItems};
-use tui::{
-    backend::CrosstermBackend,
-    layout::{Constraint, Direction, Layout},
-    style::{Color, Modifier, Style},
-    symbols::DOT,
-    terminal::Terminal,
-    text::{Span, Spans},
-    widgets::{Block, Borders, List, ListItem, Tabs},
-};
-use ui::{MenuItem, PostItem, StatefulList};
+use draw::draw;
+use guard::TerminalGuard;
+use history::History;
+use notifications::Level;
+use session::Session;
+use tui::{backend::CrosstermBackend, terminal::Terminal};
 
 enum Event<I> {
     Input(I),
+    Mouse(MouseEvent),
+    Resize,
     Tick,
 }
 
@@ -52,9 +67,12 @@ fn setup_input() -> mpsc::Receiver<Event<event::KeyEvent>> {
                 .checked_sub(last_tick.elapsed())
                 .unwrap_or_else(|| Duration::from_secs(0));
 
-            if event::poll(timeout).is_ok() {
-                if let CEvent::Key(key) = event::read().unwrap() {
-                    sender.send(Event::Input(key)).unwrap();
+            // Ticks keep coming without input, as they check if the config file changed.
+            if event::poll(timeout).unwrap_or(false) {
+                match event::read().unwrap() {
+                    CEvent::Key(key) => sender.send(Event::Input(key)).unwrap(),
+                    CEvent::Mouse(mouse) => sender.send(Event::Mouse(mouse)).unwrap(),
+                    CEvent::Resize(_, _) => sender.send(Event::Resize).unwrap(),
                 }
             }
 
@@ -127,376 +145,145 @@ fn get_time_offset(current_time: OffsetDateTime, post_time: OffsetDateTime) -> S
     String::new()
 }
 
-fn generate_list_items(
-    items: Vec<(usize, Post)>,
-    current_time: time::OffsetDateTime,
-) -> Vec<ListItem<'static>> {
-    let list_items = {
-        items
-            .iter()
-            .map(|(pos, post)| {
-                let digits = pos.to_string().chars().count();
-                let padding: String = vec![' '; digits].into_iter().collect();
-                match post {
-                    Post::Comment(comment) => PostItem::new(
-                        Spans::from(vec![
-                            Span::styled(format!("{}", pos), Style::default().fg(Color::Red)),
-                            Span::raw(format!(" {:?}", comment)),
-                        ]),
-                        Spans::from(vec![Span::styled(
-                            format!(
-                                " {}by {} | {}",
-                                padding,
-                                comment.by,
-                                get_time_offset(current_time, comment.time),
-                            ),
-                            Style::default().fg(Color::LightBlue),
-                        )]),
-                    ),
-                    Post::Job(job) => {
-                        let url = match extract_url(job.url.as_str()) {
-                            Some(uri) => uri,
-                            None => job.url.as_str(),
-                        };
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let (command, options) = match cli::parse(&args) {
+        Ok(parsed) => parsed,
+        Err(err) => {
+            eprintln!("{}\n\n{}", err, cli::USAGE);
+            std::process::exit(2);
+        }
+    };
 
-                        PostItem::new(
-                            Spans::from(vec![
-                                Span::styled(format!("{}", pos), Style::default().fg(Color::Red)),
-                                Span::styled(format!(" {}", job.title.clone()), Style::default()),
-                                Span::styled(
-                                    format!(" ({})", url),
-                                    Style::default()
-                                        .fg(Color::Gray)
-                                        .add_modifier(Modifier::ITALIC),
-                                ),
-                            ]),
-                            Spans::from(vec![Span::styled(
-                                format!(
-                                    " {}by {} | {}",
-                                    padding,
-                                    job.by,
-                                    get_time_offset(current_time, job.time),
-                                ),
-                                Style::default().fg(Color::LightBlue),
-                            )]),
-                        )
-                    }
-                    Post::Poll(poll) => {
-                        let descendants = poll.descendants;
-                        let points = poll.score;
-                        PostItem::new(
-                            Spans::from(vec![
-                                Span::styled(format!("{}", pos), Style::default().fg(Color::Red)),
-                                Span::raw(format!(" {}", poll.title.clone())),
-                            ]),
-                            Spans::from(vec![Span::styled(
-                                format!(
-                                    " {}{} {} by {} | {} | {} {}",
-                                    padding,
-                                    points,
-                                    if points == 1 { "point" } else { "points" },
-                                    poll.by,
-                                    get_time_offset(current_time, poll.time),
-                                    descendants,
-                                    if descendants == 1 {
-                                        "comment"
-                                    } else {
-                                        "comments"
-                                    },
-                                ),
-                                Style::default().fg(Color::LightBlue),
-                            )]),
-                        )
-                    }
-                    Post::PollOpt(poll_opt) => {
-                        let points = poll_opt.score;
-                        PostItem::new(
-                            Spans::from(vec![
-                                Span::styled(format!("{}", pos), Style::default().fg(Color::Red)),
-                                Span::raw(format!(" {:?}", poll_opt)),
-                            ]),
-                            Spans::from(vec![Span::styled(
-                                format!(
-                                    " {}{} {} by {} | {}",
-                                    padding,
-                                    points,
-                                    if points == 1 { "point" } else { "points" },
-                                    poll_opt.by,
-                                    get_time_offset(current_time, poll_opt.time),
-                                ),
-                                Style::default().fg(Color::LightBlue),
-                            )]),
-                        )
-                    }
-                    Post::Story(story) => {
-                        let url = match extract_url(story.url.as_str()) {
-                            Some(uri) => uri,
-                            None => story.url.as_str(),
-                        };
+    let config = Config::load(options.config.clone(), &options.overrides)?;
+    if options.print_config {
+        print!("{}", config.to_ini_string());
+        return Ok(());
+    }
 
-                        let descendants = story.descendants;
-                        let points = story.score;
+    let log_error = logging::init(config.log_level)
+        .err()
+        .map(|err| match logging::log_path() {
+            Ok(path) => format!("Couldn't open {}: {}", path.display(), err),
+            Err(_) => format!("Couldn't open the log: {}", err),
+        });
+    tracing::info!(version = env!("CARGO_PKG_VERSION"), "starting");
+
+    let client: api::Client = api::Client::new(config.api_url.clone(), reqwest::Client::new());
+    if let Some(command) = command {
+        if let Some(err) = log_error {
+            eprintln!("warning: {}", err);
+        }
+        if !matches!(command, cli::Command::CheckConfig) {
+            for error in &config.errors {
+                eprintln!("warning: config {}", error);
+            }
+        }
+        return cli::run(command, options, client, &config).await;
+    }
 
-                        PostItem::new(
-                            Spans::from(vec![
-                                Span::styled(format!("{}", pos), Style::default().fg(Color::Red)),
-                                Span::styled(format!(" {}", story.title.clone()), Style::default()),
-                                if !url.is_empty() {
-                                    Span::styled(
-                                        format!(" ({})", url),
-                                        Style::default().add_modifier(Modifier::ITALIC),
-                                    )
-                                } else {
-                                    Span::raw("")
-                                },
-                            ]),
-                            Spans::from(vec![Span::styled(
-                                format!(
-                                    " {}{} {} by {} | {} | {} {}",
-                                    padding,
-                                    points,
-                                    if points == 1 { "point" } else { "points" },
-                                    story.by,
-                                    get_time_offset(current_time, story.time),
-                                    descendants,
-                                    if descendants == 1 {
-                                        "comment"
-                                    } else {
-                                        "comments"
-                                    },
-                                ),
-                                Style::default().fg(Color::LightBlue),
-                            )]),
-                        )
-                    }
-                }
-            })
-            .flat_map(Vec::<ListItem>::from)
-            .collect::<Vec<ListItem>>()
+    let history = History::new()?;
+    let bookmarks = Bookmarks::new()?;
+    let mut session = if config.restore_session {
+        Some(Session::new()?)
+    } else {
+        None
     };
-
-    list_items
-}
-
-#[tokio::main]
-async fn main() -> Result<(), Box<dyn std::error::Error>> {
-    let client: api::Client = api::Client::new(api::BASE_URL.to_string(), reqwest::Client::new());
-    let mut top_items: TopItems = TopItems::new(client.clone());
-    let mut new_items: NewItems = NewItems::new(client);
-    let mut current_time: OffsetDateTime = OffsetDateTime::now_utc();
-    let config = Config::new()?;
+    let saved = session.as_mut().and_then(|session| session.saved.take());
+    let read_errors: Vec<String> = history
+        .read_error
+        .iter()
+        .chain(&bookmarks.read_error)
+        .chain(
+            session
+                .as_ref()
+                .and_then(|session| session.read_error.as_ref()),
+        )
+        .cloned()
+        .collect();
 
     let stdout = std::io::stdout();
     let backend = CrosstermBackend::new(stdout);
     let mut terminal = Terminal::new(backend)?;
 
     terminal.clear()?;
-    enable_raw_mode()?;
+    // Restores the terminal when main returns, also if it returns an error.
+    let _guard = TerminalGuard::new(config.mouse)?;
 
     let receiver = setup_input();
 
-    let mut stateful_list = match config.default_view {
-        MenuItem::Top => {
-            top_items.list =
-                generate_list_items(top_items.get_vec(config.max_items).await?, current_time);
-            StatefulList::new(top_items.list.clone(), config.scroll_past_list)
-        }
-        MenuItem::New => {
-            new_items.list =
-                generate_list_items(new_items.get_vec(config.max_items).await?, current_time);
-            StatefulList::new(new_items.list.clone(), config.scroll_past_list)
-        }
-    };
-    stateful_list.next();
-
-    let mut active_menu_item = config.default_view;
-    let tabs: Vec<Spans> = ["Top", "New"].iter().cloned().map(Spans::from).collect();
+    let mut app = App::new(config, client, history, bookmarks, session);
+    app.report_config_errors();
+    for error in read_errors {
+        app.notifications.error(error);
+    }
+    if let Some(err) = log_error {
+        app.notifications.warning(err);
+    }
+    match saved {
+        Some(saved) => app.restore_session(saved, &mut terminal).await?,
+        None => app.load(false).await,
+    }
 
     loop {
-        terminal.draw(|frame| match active_menu_item {
-            MenuItem::Top => {
-                if stateful_list.items != top_items.list {
-                    stateful_list.items = generate_list_items(
-                        block_on(top_items.get_vec(config.max_items)).unwrap_or_else(|_| vec![]),
-                        current_time,
-                    );
-                }
-
-                let chunks = Layout::default()
-                    .direction(Direction::Vertical)
-                    .constraints([Constraint::Length(3), Constraint::Min(2)].as_ref())
-                    .split(frame.size());
-
-                let list = List::new(stateful_list.items.clone())
-                    .block(
-                        Block::default().borders(Borders::BOTTOM | Borders::LEFT | Borders::RIGHT),
-                    )
-                    .highlight_style(Style::default().fg(Color::Green));
-
-                let tab = Tabs::new(tabs.clone())
-                    .select(active_menu_item.into())
-                    .block(Block::default().borders(Borders::TOP | Borders::LEFT | Borders::RIGHT))
-                    .style(Style::default().fg(Color::White))
-                    .highlight_style(Style::default().fg(Color::Yellow))
-                    .divider(DOT);
+        terminal.draw(|frame| draw(frame, &mut app))?;
 
-                frame.render_widget(tab, chunks[0]);
-                frame.render_stateful_widget(list, chunks[1], &mut stateful_list.state);
-            }
-            MenuItem::New => {
-                if stateful_list.items != new_items.list {
-                    stateful_list.items = generate_list_items(
-                        block_on(new_items.get_vec(config.max_items)).unwrap_or_else(|_| vec![]),
-                        current_time,
-                    );
+        let quit = match receiver.recv()? {
+            Event::Input(event) if app.prompt.is_some() => {
+                match app.handle_prompt_key(event.code).await {
+                    Some(command) => !app.run_command(command, &mut terminal).await?,
+                    None => false,
                 }
-
-                let chunks = Layout::default()
-                    .direction(Direction::Vertical)
-                    .constraints([Constraint::Length(3), Constraint::Min(2)].as_ref())
-                    .split(frame.size());
-
-                let list = List::new(stateful_list.items.clone())
-                    .block(
-                        Block::default().borders(Borders::BOTTOM | Borders::LEFT | Borders::RIGHT),
-                    )
-                    .highlight_style(Style::default().fg(Color::Green));
-
-                let tab = Tabs::new(tabs.clone())
-                    .select(active_menu_item.into())
-                    .block(Block::default().borders(Borders::TOP | Borders::LEFT | Borders::RIGHT))
-                    .style(Style::default().fg(Color::White))
-                    .highlight_style(Style::default().fg(Color::Yellow))
-                    .divider(DOT);
-
-                frame.render_widget(tab, chunks[0]);
-                frame.render_stateful_widget(list, chunks[1], &mut stateful_list.state);
             }
-        })?;
-
-        match receiver.recv()? {
+            // Any key closes the error popup.
+            Event::Input(_)
+                if app
+                    .notifications
+                    .current()
+                    .is_some_and(|notification| notification.level == Level::Error) =>
+            {
+                app.notifications.dismiss();
+                false
+            }
+            Event::Input(event) if app.overlay.is_some() => {
+                app.handle_overlay_key(event);
+                false
+            }
             Event::Input(event) => {
-                if config.view_comments.contains(&event.code) {
-                    if let Some(index) = stateful_list.state.selected() {
-                        let items = match active_menu_item {
-                            MenuItem::Top => {
-                                let top_items = &mut top_items;
-                                block_on(top_items.get_vec(config.max_items))?
-                            }
-                            MenuItem::New => {
-                                let new_items = &mut new_items;
-                                block_on(new_items.get_vec(config.max_items))?
-                            }
-                        };
-
-                        if let Some(item) = items.get(index / 2) {
-                            match item {
-                                (_, Post::Comment(comment)) => eprintln!("{:?}", comment),
-                                (_, Post::Job(job)) => match webbrowser::open(&format!(
-                                    "https://news.ycombinator.com/item?id={}",
-                                    job.id
-                                )) {
-                                    Ok(_) => {
-                                        terminal.clear().expect("Failed to clear the terminal");
-                                    }
-                                    Err(error) => {
-                                        eprintln!("{:?}", error);
-                                    }
-                                },
-                                (_, Post::Poll(poll)) => eprintln!("{:?}", poll),
-                                (_, Post::PollOpt(poll_opt)) => eprintln!("{:?}", poll_opt),
-                                (_, Post::Story(story)) => {
-                                    match webbrowser::open(&format!(
-                                        "https://news.ycombinator.com/item?id={}",
-                                        story.id
-                                    )) {
-                                        Ok(_) => {
-                                            terminal.clear().expect("Failed to clear the terminal")
-                                        }
-                                        Err(error) => {
-                                            eprintln!("{:?}", error);
-                                        }
-                                    }
-                                }
-                            }
-                        }
-                    }
-                } else if config.quit.contains(&event.code) {
-                    disable_raw_mode()?;
-                    terminal.clear()?;
-                    terminal.show_cursor()?;
-                    break;
-                } else if config.open_article.contains(&event.code) {
-                    if let Some(index) = stateful_list.state.selected() {
-                        let items = match active_menu_item {
-                            MenuItem::Top => {
-                                let top_items = &mut top_items;
-                                block_on(top_items.get_vec(config.max_items))?
-                            }
-                            MenuItem::New => {
-                                let new_items = &mut new_items;
-                                block_on(new_items.get_vec(config.max_items))?
-                            }
-                        };
-
-                        if let Some(item) = items.get(index / 2) {
-                            match item {
-                                (_, Post::Comment(comment)) => eprintln!("{:?}", comment),
-                                (_, Post::Job(job)) => match webbrowser::open(job.url.as_str()) {
-                                    Ok(_) => {
-                                        terminal.clear().expect("Failed to clear the terminal");
-                                    }
-                                    Err(error) => {
-                                        eprintln!("{:?}", error);
-                                    }
-                                },
-                                (_, Post::Poll(poll)) => eprintln!("{:?}", poll),
-                                (_, Post::PollOpt(poll_opt)) => eprintln!("{:?}", poll_opt),
-                                (_, Post::Story(story)) => {
-                                    match webbrowser::open(story.url.as_str()) {
-                                        Ok(_) => {
-                                            terminal.clear().expect("Failed to clear the terminal")
-                                        }
-                                        Err(error) => {
-                                            eprintln!("{:?}", error);
-                                        }
-                                    }
-                                }
-                            }
-                        }
-                    }
-                } else if config.left.contains(&event.code) || config.right.contains(&event.code) {
-                    active_menu_item.scroll();
-                } else if config.up.contains(&event.code) {
-                    stateful_list.previous();
-                } else if config.down.contains(&event.code) {
-                    stateful_list.next();
-                } else if config.refresh.contains(&event.code) {
-                    match active_menu_item {
-                        MenuItem::Top => {
-                            current_time = OffsetDateTime::now_utc();
-                            top_items.refresh(config.max_items).await?;
-                            top_items.list = generate_list_items(
-                                top_items.get_vec(config.max_items).await?,
-                                current_time,
-                            );
-                            stateful_list.items = top_items.list.clone();
-                        }
-                        MenuItem::New => {
-                            current_time = OffsetDateTime::now_utc();
-                            new_items.refresh(config.max_items).await?;
-                            new_items.list = generate_list_items(
-                                new_items.get_vec(config.max_items).await?,
-                                current_time,
-                            );
-                            stateful_list.items = new_items.list.clone();
-                        }
+                app.notifications.dismiss();
+                let keys = app
+                    .pending_keys
+                    .push(event, app.config.keybindings.values());
+                match keys.and_then(|keys| app.config.action(&keys)) {
+                    Some(action) => {
+                        let count = app.pending_keys.count.take();
+                        !app.perform(action, count, &mut terminal).await?
                     }
+                    None => false,
+                }
+            }
+            Event::Mouse(event) if app.prompt.is_none() && app.overlay.is_none() => {
+                !app.handle_mouse(event, &mut terminal).await?
+            }
+            Event::Mouse(_) => false,
+            // The next draw lays out the list for the new size.
+            Event::Resize => false,
+            Event::Tick => {
+                if app.config.changed() {
+                    app.reload_config().await;
                 }
+                false
             }
-            Event::Tick => {}
+        };
+
+        if quit {
+            terminal.clear()?;
+            break;
         }
     }
 
+    app.save_session()?;
+
     Ok(())
 }
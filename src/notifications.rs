@@ -0,0 +1,99 @@
+use std::fmt;
+use time::OffsetDateTime;
+
+/// The most notifications kept in the log, older ones are dropped.
+const MAX_LOG: usize = 200;
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Level {
+    Info,
+    /// Mistakes like an unknown command, shown in the status bar like info.
+    Warning,
+    /// Failures like a feed that couldn't be fetched, shown in a popup.
+    Error,
+}
+
+impl fmt::Display for Level {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Level::Info => "info",
+            Level::Warning => "warning",
+            Level::Error => "error",
+        })
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Notification {
+    pub level: Level,
+    pub message: String,
+    pub time: OffsetDateTime,
+}
+
+/// The messages for the user. The last one is shown until the next key press, and all of them
+/// are kept in the log.
+#[derive(Debug, Default)]
+pub struct Notifications {
+    pub log: Vec<Notification>,
+    /// Whether the last notification is still shown.
+    shown: bool,
+    /// A warning shown on a line of its own until it's dismissed, so later messages can't
+    /// replace it, like the problems found in the config.
+    pub banner: Option<String>,
+}
+
+impl Notifications {
+    pub fn info(&mut self, message: impl Into<String>) {
+        self.push(Level::Info, message.into());
+    }
+
+    pub fn warning(&mut self, message: impl Into<String>) {
+        self.push(Level::Warning, message.into());
+    }
+
+    pub fn error(&mut self, message: impl Into<String>) {
+        self.push(Level::Error, message.into());
+    }
+
+    /// Shows the warning in the banner, replacing the one shown before.
+    pub fn set_banner(&mut self, message: impl Into<String>) {
+        let message = message.into();
+        self.add_to_log(Level::Warning, message.clone());
+        self.banner = Some(message);
+    }
+
+    pub fn dismiss_banner(&mut self) {
+        self.banner = None;
+    }
+
+    /// Adds the notification to the log without showing it, and writes it to the log file too.
+    pub fn add_to_log(&mut self, level: Level, message: String) {
+        match level {
+            Level::Info => tracing::info!(message = %message),
+            Level::Warning => tracing::warn!(message = %message),
+            Level::Error => tracing::error!(message = %message),
+        }
+        if self.log.len() >= MAX_LOG {
+            self.log.remove(0);
+        }
+        self.log.push(Notification {
+            level,
+            message,
+            time: OffsetDateTime::now_utc(),
+        });
+    }
+
+    fn push(&mut self, level: Level, message: String) {
+        self.add_to_log(level, message);
+        self.shown = true;
+    }
+
+    /// Returns the last notification, unless it was dismissed.
+    pub fn current(&self) -> Option<&Notification> {
+        self.log.last().filter(|_| self.shown)
+    }
+
+    pub fn dismiss(&mut self) {
+        self.shown = false;
+    }
+}
@@ -0,0 +1,660 @@
+use crate::{
+    bookmarks::Bookmarks,
+    config::Config,
+    export, extract_url, get_time_offset,
+    history::History,
+    items::Change,
+    session::{FeedState, PaneState, Position, ThreadState},
+    template::Field,
+    theme::Theme,
+    ui::{self, LayoutMode, MenuItem, StatefulList},
+};
+use api::Post;
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::convert::TryFrom;
+use time::OffsetDateTime;
+use tui::{
+    layout::Rect,
+    style::{Modifier, Style},
+    text::{Span, Spans},
+    widgets::ListItem,
+};
+
+/// Writes a change like `+12` or `-3`, or nothing if there was no change.
+fn signed_change(change: i64) -> String {
+    match change {
+        0 => String::new(),
+        change if change > 0 => format!("+{}", change),
+        change => change.to_string(),
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn generate_list_items(
+    items: &[(usize, Post)],
+    current_time: time::OffsetDateTime,
+    config: &Config,
+    history: &History,
+    bookmarks: &Bookmarks,
+    muted: &HashSet<u32>,
+    changes: Option<&HashMap<u32, Change>>,
+    width: usize,
+    compact: bool,
+) -> Vec<ListItem<'static>> {
+    let theme = &config.theme;
+    let templates = if compact {
+        std::slice::from_ref(&config.compact_row)
+    } else {
+        config.rows.as_slice()
+    };
+
+    items
+        .iter()
+        .flat_map(|(pos, post)| {
+            let bookmark = bookmarks.get(post.id());
+            let title = match post {
+                Post::Comment(_) | Post::PollOpt(_) => export::render_html(post.text(), false)
+                    .lines()
+                    .next()
+                    .unwrap_or_default()
+                    .to_string(),
+                _ => post.title().to_string(),
+            };
+            // Muted posts are only listed while the hidden posts are shown.
+            let muted = muted.contains(&post.id());
+            let title = if muted {
+                format!("[muted] {}", title)
+            } else {
+                title
+            };
+            let score = match post {
+                Post::Story(_) | Post::Poll(_) | Post::PollOpt(_) => Some(post.score()),
+                Post::Job(_) | Post::Comment(_) => None,
+            };
+            let comments = match post {
+                Post::Story(_) | Post::Poll(_) => Some(post.descendants()),
+                Post::Job(_) | Post::Comment(_) | Post::PollOpt(_) => None,
+            };
+            let change = changes.and_then(|changes| changes.get(&post.id()));
+            let growth_style = |growth: i64| if growth < 0 { theme.down } else { theme.up };
+
+            let value = |field| match field {
+                Field::Rank => (pos.to_string(), theme.rank),
+                Field::Pad => (" ".repeat(pos.to_string().len()), Style::default()),
+                Field::Title => (
+                    title.clone(),
+                    config
+                        .highlights
+                        .style(post)
+                        .map_or(theme.title, |style| theme.title.patch(style)),
+                ),
+                Field::Domain => (
+                    extract_url(post.url()).unwrap_or(post.url()).to_string(),
+                    theme.domain,
+                ),
+                Field::Url => (post.url().to_string(), theme.domain),
+                Field::By => (post.by().to_string(), theme.metadata),
+                Field::Age => (get_time_offset(current_time, post.time()), theme.metadata),
+                Field::Score => (
+                    score.map(|score| score.to_string()).unwrap_or_default(),
+                    theme.metadata,
+                ),
+                Field::ScoreLabel => (
+                    score
+                        .map(|score| {
+                            format!("{} {}", score, if score == 1 { "point" } else { "points" })
+                        })
+                        .unwrap_or_default(),
+                    theme.metadata,
+                ),
+                Field::Comments => (
+                    comments.map(|count| count.to_string()).unwrap_or_default(),
+                    theme.metadata,
+                ),
+                Field::CommentsLabel => (
+                    comments
+                        .map(|count| {
+                            format!(
+                                "{} {}",
+                                count,
+                                if count == 1 { "comment" } else { "comments" }
+                            )
+                        })
+                        .unwrap_or_default(),
+                    theme.metadata,
+                ),
+                Field::NewComments => (
+                    match history.new_comments(post.id(), post.descendants()) {
+                        Some(count) if count > 0 => count.to_string(),
+                        _ => String::new(),
+                    },
+                    theme.metadata,
+                ),
+                Field::RankChange => match change {
+                    Some(Change::New) => (String::from("NEW"), theme.new),
+                    Some(Change::Moved { rank, .. }) if *rank > 0 => {
+                        (format!("▲{}", rank), theme.up)
+                    }
+                    Some(Change::Moved { rank, .. }) if *rank < 0 => {
+                        (format!("▼{}", -rank), theme.down)
+                    }
+                    _ => (String::new(), theme.metadata),
+                },
+                Field::ScoreChange => match (change, score) {
+                    (Some(Change::Moved { score: growth, .. }), Some(_)) => {
+                        (signed_change(*growth), growth_style(*growth))
+                    }
+                    _ => (String::new(), theme.metadata),
+                },
+                Field::CommentsChange => match (change, comments) {
+                    (
+                        Some(Change::Moved {
+                            comments: growth, ..
+                        }),
+                        Some(_),
+                    ) => (signed_change(*growth), growth_style(*growth)),
+                    _ => (String::new(), theme.metadata),
+                },
+                Field::New => (
+                    if history.is_new(post.id()) { "new" } else { "" }.to_string(),
+                    theme.new,
+                ),
+                Field::Bookmark => (
+                    if bookmark.is_some() { "★" } else { "" }.to_string(),
+                    theme.bookmark,
+                ),
+                Field::Tags => (
+                    bookmark
+                        .map(|bookmark| bookmark.tags.join(", "))
+                        .unwrap_or_default(),
+                    theme.tags,
+                ),
+                Field::Note => (
+                    bookmark
+                        .map(|bookmark| bookmark.note.clone())
+                        .unwrap_or_default(),
+                    theme.note,
+                ),
+                Field::Kind => (
+                    match post {
+                        Post::Job(_) => "job",
+                        Post::Story(_) => "story",
+                        Post::Comment(_) => "comment",
+                        Post::Poll(_) => "poll",
+                        Post::PollOpt(_) => "pollopt",
+                    }
+                    .to_string(),
+                    theme.metadata,
+                ),
+                Field::Id => (post.id().to_string(), theme.metadata),
+            };
+
+            let style = if muted || history.is_visited(post.id()) {
+                theme.visited
+            } else {
+                Style::default()
+            };
+
+            templates
+                .iter()
+                .enumerate()
+                .map(|(line, template)| {
+                    // The text around the fields of the first line is left unstyled like the
+                    // title, the lines below it are metadata.
+                    let text_style = if line == 0 {
+                        Style::default()
+                    } else {
+                        theme.metadata
+                    };
+                    let (mut spans, title) = template.render(value, text_style);
+
+                    // Shortens the title, so the domain and markers after it stay visible.
+                    if let Some(title) = title.filter(|_| width > 0) {
+                        ui::fit_span(&mut spans, title, width);
+                    }
+
+                    ListItem::new(Spans::from(spans)).style(style)
+                })
+                .collect::<Vec<_>>()
+        })
+        .collect()
+}
+
+/// Lays out the thread for its pane: the post like in the preview, followed by every reply
+/// indented under its parent, wrapped to the width. Also returns the line each post starts
+/// on, in the order they're shown.
+///
+/// The replies of collapsed posts are left out, and so is the text of collapsed replies,
+/// leaving the number of posts hidden after their author.
+pub fn thread_lines(
+    thread: &api::Thread,
+    collapsed: &BTreeSet<u32>,
+    current_time: OffsetDateTime,
+    theme: &Theme,
+    width: usize,
+) -> (Vec<Spans<'static>>, Vec<usize>) {
+    let post = &thread.post;
+    let mut lines: Vec<Spans> = ui::wrap(post.title(), width)
+        .into_iter()
+        .filter(|line| !line.is_empty())
+        .map(|line| Spans::from(Span::styled(line, theme.title.add_modifier(Modifier::BOLD))))
+        .collect();
+
+    if !post.url().is_empty() {
+        lines.push(Spans::from(Span::styled(
+            ui::truncate(post.url(), width),
+            theme.domain,
+        )));
+    }
+
+    let mut metadata = format!(
+        "{} points by {} | {} | {} comments",
+        post.score(),
+        post.by(),
+        get_time_offset(current_time, post.time()),
+        post.descendants()
+    );
+    let collapse = collapsed.contains(&post.id());
+    if collapse {
+        metadata.push_str(&format!(" [+{}]", count_replies(thread)));
+    }
+    lines.extend(
+        ui::wrap(&metadata, width)
+            .into_iter()
+            .map(|line| Spans::from(Span::styled(line, theme.metadata))),
+    );
+
+    let text = export::post_text(post, false);
+    if !text.is_empty() {
+        lines.push(Spans::default());
+        lines.extend(ui::wrap(&text, width).into_iter().map(Spans::from));
+    }
+
+    let mut starts = vec![0];
+    if !collapse {
+        for reply in &thread.replies {
+            reply_lines(
+                &mut lines,
+                &mut starts,
+                reply,
+                collapsed,
+                0,
+                current_time,
+                theme,
+                width,
+            );
+        }
+    }
+
+    (lines, starts)
+}
+
+/// Counts every reply in the thread, including the replies to replies.
+fn count_replies(thread: &api::Thread) -> usize {
+    thread
+        .replies
+        .iter()
+        .map(|reply| 1 + count_replies(reply))
+        .sum()
+}
+
+/// Adds the reply and its own replies to the lines of a thread, indented by their depth.
+#[allow(clippy::too_many_arguments)]
+fn reply_lines(
+    lines: &mut Vec<Spans<'static>>,
+    starts: &mut Vec<usize>,
+    thread: &api::Thread,
+    collapsed: &BTreeSet<u32>,
+    depth: usize,
+    current_time: OffsetDateTime,
+    theme: &Theme,
+    width: usize,
+) {
+    // Deep replies stop being indented further to keep some room for their text.
+    let indent = " ".repeat((depth * 2).min(width / 2));
+    let post = &thread.post;
+
+    let age = get_time_offset(current_time, post.time());
+    let mut header = match post.by() {
+        "" => age,
+        by => format!("{} | {}", by, age),
+    };
+    if collapsed.contains(&post.id()) {
+        // Counts the reply itself as well, as its text is hidden too.
+        header.push_str(&format!(" [+{}]", 1 + count_replies(thread)));
+        lines.push(Spans::default());
+        starts.push(lines.len());
+        lines.push(Spans::from(vec![
+            Span::raw(indent),
+            Span::styled(header, theme.metadata),
+        ]));
+        return;
+    }
+    lines.push(Spans::default());
+    starts.push(lines.len());
+    lines.push(Spans::from(vec![
+        Span::raw(indent.clone()),
+        Span::styled(header, theme.metadata),
+    ]));
+    lines.extend(
+        ui::wrap(&export::post_text(post, false), width - indent.len())
+            .into_iter()
+            .map(|line| Spans::from(format!("{}{}", indent, line))),
+    );
+
+    for reply in &thread.replies {
+        reply_lines(
+            lines,
+            starts,
+            reply,
+            collapsed,
+            depth + 1,
+            current_time,
+            theme,
+            width,
+        );
+    }
+}
+
+/// A pane of the split view. The first pane always shows a feed.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Focus {
+    Main,
+    Split,
+}
+
+/// A feed shown in a pane, with its own posts, selection and scroll.
+pub struct FeedPane {
+    pub feed: MenuItem,
+    /// Where the other feeds were left, to go back there when they are shown again.
+    positions: HashMap<MenuItem, Position>,
+    pub posts: Vec<(usize, Post)>,
+    /// The ids of the posts of the feed muted by the filters, which are only in `posts` while
+    /// the hidden posts are shown.
+    pub muted: HashSet<u32>,
+    pub stateful_list: StatefulList<ListItem<'static>>,
+    /// The layout and the size of the list when it was last drawn. The rows of the list are
+    /// generated again when they change.
+    pub layout: LayoutMode,
+    pub list_width: u16,
+    pub list_height: u16,
+    /// Where the rows of the list were last drawn, to find what was clicked.
+    pub list_area: Rect,
+}
+
+impl FeedPane {
+    pub fn new(feed: MenuItem, scroll_past_list: bool) -> FeedPane {
+        FeedPane {
+            feed,
+            positions: HashMap::new(),
+            posts: Vec::new(),
+            muted: HashSet::new(),
+            stateful_list: StatefulList::new(Vec::new(), scroll_past_list),
+            layout: LayoutMode::Normal,
+            list_width: 0,
+            list_height: 0,
+            list_area: Rect::default(),
+        }
+    }
+
+    pub fn update_list(
+        &mut self,
+        current_time: OffsetDateTime,
+        config: &Config,
+        history: &History,
+        bookmarks: &Bookmarks,
+        changes: Option<&HashMap<u32, Change>>,
+    ) {
+        let compact = self.layout == LayoutMode::Compact;
+        self.stateful_list.set_items(
+            generate_list_items(
+                &self.posts,
+                current_time,
+                config,
+                history,
+                bookmarks,
+                &self.muted,
+                changes,
+                self.list_width as usize,
+                compact,
+            ),
+            if compact { 1 } else { config.rows.len() },
+        );
+    }
+
+    pub fn selected_post(&self) -> Option<&Post> {
+        self.posts
+            .get(self.stateful_list.selected_post()?)
+            .map(|(_, post)| post)
+    }
+
+    /// Returns the pane of a saved session, which opens its feed where it was left once it's
+    /// loaded.
+    pub fn restored(state: FeedState, scroll_past_list: bool) -> FeedPane {
+        FeedPane {
+            positions: state.positions,
+            ..FeedPane::new(state.feed, scroll_past_list)
+        }
+    }
+
+    /// Returns the feed of the pane and where every feed shown in it was left, to save it in
+    /// the session.
+    pub fn state(&self) -> FeedState {
+        let mut positions = self.positions.clone();
+        if let Some(position) = self.position() {
+            positions.insert(self.feed, position);
+        }
+        FeedState {
+            feed: self.feed,
+            positions,
+        }
+    }
+
+    /// Shows the posts of the feed, remembering where the feed shown before was left if it's
+    /// another one. Returns where the feed was left, to restore it once the list is updated.
+    pub fn show(
+        &mut self,
+        feed: MenuItem,
+        posts: Vec<(usize, Post)>,
+        muted: HashSet<u32>,
+    ) -> Option<Position> {
+        let position = if feed == self.feed {
+            self.position()
+        } else {
+            if let Some(position) = self.position() {
+                self.positions.insert(self.feed, position);
+            }
+            self.feed = feed;
+            None
+        };
+        // A restored pane has no posts yet, but knows where its feed was left.
+        let position = position.or_else(|| self.positions.remove(&feed));
+
+        self.posts = posts;
+        self.muted = muted;
+        position
+    }
+
+    fn position(&self) -> Option<Position> {
+        let index = self.stateful_list.selected_post()?;
+        Some(Position {
+            id: self.posts.get(index)?.1.id(),
+            index,
+            row: self.stateful_list.selected_row()?,
+        })
+    }
+
+    /// Selects the post of the position again, wherever its rank moved it to, and scrolls it
+    /// back to the same row.
+    pub fn restore(&mut self, position: Position) {
+        let index = self
+            .posts
+            .iter()
+            .position(|(_, post)| post.id() == position.id)
+            .unwrap_or(position.index);
+        self.stateful_list.select_post_at_row(index, position.row);
+    }
+}
+
+/// The comment thread of a post shown in a pane, scrolled by lines, with one of its posts
+/// selected.
+pub struct ThreadPane {
+    pub thread: api::Thread,
+    pub scroll: u16,
+    /// The index of the selected post, in the order they're shown.
+    pub selected: usize,
+    /// The ids of the posts whose replies are hidden.
+    pub collapsed: BTreeSet<u32>,
+    /// The lines of the thread wrapped to the width of the pane, which are only wrapped again
+    /// when the width changes, and the line each post starts on.
+    pub lines: Vec<Spans<'static>>,
+    pub starts: Vec<usize>,
+    pub width: u16,
+    /// Where the thread was last drawn, and how many of its lines fit.
+    pub area: Rect,
+    pub height: u16,
+}
+
+impl ThreadPane {
+    pub fn new(thread: api::Thread) -> ThreadPane {
+        ThreadPane {
+            thread,
+            scroll: 0,
+            selected: 0,
+            collapsed: BTreeSet::new(),
+            lines: Vec::new(),
+            starts: Vec::new(),
+            width: 0,
+            area: Rect::default(),
+            height: 0,
+        }
+    }
+
+    pub fn state(&self) -> ThreadState {
+        ThreadState {
+            id: self.thread.post.id(),
+            scroll: self.scroll,
+            selected: self.selected,
+            collapsed: self.collapsed.clone(),
+        }
+    }
+
+    /// Scrolls and selects like the state, and collapses the same posts, once the thread was
+    /// loaded again.
+    pub fn restore(&mut self, state: ThreadState) {
+        self.scroll = state.scroll;
+        self.collapsed = state.collapsed;
+        self.selected = state.selected.min(self.posts().len().saturating_sub(1));
+        self.width = 0;
+    }
+
+    /// Returns the post of the thread followed by every reply that isn't collapsed, in the
+    /// order they're shown.
+    pub fn posts(&self) -> Vec<&Post> {
+        fn add<'a>(thread: &'a api::Thread, collapsed: &BTreeSet<u32>, posts: &mut Vec<&'a Post>) {
+            posts.push(&thread.post);
+            if !collapsed.contains(&thread.post.id()) {
+                for reply in &thread.replies {
+                    add(reply, collapsed, posts);
+                }
+            }
+        }
+
+        let mut posts = Vec::new();
+        add(&self.thread, &self.collapsed, &mut posts);
+        posts
+    }
+
+    /// Hides the replies of the selected post, or shows them again.
+    pub fn toggle_collapsed(&mut self) {
+        if let Some(id) = self.selected_post().map(Post::id) {
+            if !self.collapsed.remove(&id) {
+                self.collapsed.insert(id);
+            }
+            // Lays out the thread again when it's drawn.
+            self.width = 0;
+        }
+    }
+
+    pub fn selected_post(&self) -> Option<&Post> {
+        self.posts().get(self.selected).copied()
+    }
+
+    /// Returns the index of the post shown on the line.
+    pub fn post_at(&self, line: usize) -> usize {
+        self.starts
+            .iter()
+            .rposition(|start| *start <= line)
+            .unwrap_or(0)
+    }
+
+    /// Selects the post at the index, or the last one, and scrolls so it can be seen, as much
+    /// of it as fits.
+    pub fn select(&mut self, index: usize) {
+        self.selected = index.min(self.posts().len().saturating_sub(1));
+
+        let start = match self.starts.get(self.selected) {
+            Some(start) => *start,
+            // The lines aren't known before the thread was first drawn.
+            None => return,
+        };
+        // Replies are separated by a blank line, which isn't part of the previous one.
+        let end = self
+            .starts
+            .get(self.selected + 1)
+            .map_or(self.lines.len(), |next| next - 1);
+        let mut scroll = self.scroll as usize;
+        scroll = scroll.max(end.saturating_sub(self.height.max(1) as usize));
+        scroll = scroll.min(start);
+        self.scroll = u16::try_from(scroll).unwrap_or(u16::MAX);
+    }
+
+    pub fn move_selection(&mut self, count: isize) {
+        let index = if count < 0 {
+            self.selected.saturating_sub(count.unsigned_abs())
+        } else {
+            self.selected.saturating_add(count as usize)
+        };
+        self.select(index);
+    }
+
+    /// Scrolls by the number of lines, and selects the first post starting in view if the
+    /// selected one went out of it.
+    pub fn scroll_by(&mut self, count: isize) {
+        let end = self.lines.len().saturating_sub(self.height as usize);
+        let scroll = if count < 0 {
+            (self.scroll as usize).saturating_sub(count.unsigned_abs())
+        } else {
+            (self.scroll as usize).saturating_add(count as usize)
+        }
+        .min(end);
+        self.scroll = u16::try_from(scroll).unwrap_or(u16::MAX);
+
+        let visible = scroll..scroll + self.height.max(1) as usize;
+        let selected_visible = self
+            .starts
+            .get(self.selected)
+            .is_none_or(|start| visible.contains(start));
+        if !selected_visible {
+            self.selected = self
+                .starts
+                .iter()
+                .position(|start| visible.contains(start))
+                .unwrap_or_else(|| self.post_at(scroll));
+        }
+    }
+}
+
+pub enum Pane {
+    Feed(FeedPane),
+    Thread(ThreadPane),
+}
+
+impl Pane {
+    pub fn state(&self) -> PaneState {
+        match self {
+            Pane::Feed(feed) => PaneState::Feed(feed.state()),
+            Pane::Thread(thread) => PaneState::Thread(thread.state()),
+        }
+    }
+}
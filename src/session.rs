@@ -0,0 +1,80 @@
+use crate::{files, ui::MenuItem};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{BTreeSet, HashMap},
+    path::PathBuf,
+};
+
+/// Where a feed was left: the selected post, and the row of the list it was shown on.
+#[derive(Copy, Clone, Debug, Serialize, Deserialize)]
+pub struct Position {
+    pub id: u32,
+    /// The position of the post in the list, to select a post near it if it's gone.
+    pub index: usize,
+    pub row: usize,
+}
+
+/// The feed shown in a pane, and where every feed shown in it was left.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct FeedState {
+    pub feed: MenuItem,
+    #[serde(default)]
+    pub positions: HashMap<MenuItem, Position>,
+}
+
+/// The comment thread of the post with the id, how far it was scrolled, the selected post and
+/// the posts whose replies were collapsed.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ThreadState {
+    pub id: u32,
+    pub scroll: u16,
+    #[serde(default)]
+    pub selected: usize,
+    #[serde(default)]
+    pub collapsed: BTreeSet<u32>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum PaneState {
+    Feed(FeedState),
+    Thread(ThreadState),
+}
+
+/// The panes that were open when hntui quit.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SavedSession {
+    pub main: FeedState,
+    #[serde(default)]
+    pub split: Option<PaneState>,
+    #[serde(default)]
+    pub split_focused: bool,
+}
+
+/// The session file, which lets hntui open where it was left when `general.restore_session`
+/// is set.
+#[derive(Debug, Default)]
+pub struct Session {
+    path: PathBuf,
+    /// The session saved when hntui last quit, if there is one.
+    pub saved: Option<SavedSession>,
+    /// Why the session file couldn't be read, if it couldn't.
+    pub read_error: Option<String>,
+}
+
+impl Session {
+    pub fn new() -> Result<Self, std::io::Error> {
+        let path = files::data_file("session.json")?;
+        let (saved, read_error) = files::load_json(&path, "starting a new session")?;
+
+        Ok(Session {
+            path,
+            saved,
+            read_error,
+        })
+    }
+
+    pub fn write(&self, session: &SavedSession) -> Result<(), std::io::Error> {
+        std::fs::write(&self.path, serde_json::to_string(session)?)
+    }
+}
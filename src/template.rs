@@ -0,0 +1,382 @@
+use crate::ui::parse_style;
+use std::{fmt, str::FromStr};
+use tui::{style::Style, text::Span};
+
+/// The default lines of the rows of the list. The third one is empty, so rows take up two lines.
+pub const DEFAULT_LINES: [&str; 3] = [
+    r"{rank} {title}[ ({domain})][ {new}][ {rank_change}][ {bookmark}][ \[{tags}\]]",
+    "{pad} [{score_label} ][({score_change}) ]by {by} | {age}[ | {comments_label}][ ({comments_change})][ (+{new_comments} new)][ | {note}]",
+    "",
+];
+
+/// The default row of the compact layout, which only has room for one line per post.
+pub const DEFAULT_COMPACT: &str = r"{rank} {title}[ ({domain})][ {new}][ {rank_change}][ {bookmark}][ \[{tags}\]][ {score}p][ {comments}c][ (+{new_comments} new)]";
+
+/// The values a row template can show, written as `{name}` in the template.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Field {
+    Rank,
+    /// Spaces as wide as the rank, to line the rows of a post up under the title.
+    Pad,
+    Title,
+    Domain,
+    Url,
+    By,
+    Age,
+    Score,
+    /// The score with its unit, like `1 point` or `120 points`.
+    ScoreLabel,
+    Comments,
+    /// The comment count with its unit, like `1 comment` or `20 comments`.
+    CommentsLabel,
+    /// The number of comments added since the comments were last opened.
+    NewComments,
+    /// How many places the post rose or fell in the last refresh, like `▲3` or `▼5`, or `NEW`
+    /// if it wasn't in the feed before.
+    RankChange,
+    /// How much the score grew in the last refresh, like `+12`.
+    ScoreChange,
+    /// How many comments were added in the last refresh, like `+4`.
+    CommentsChange,
+    New,
+    Bookmark,
+    Tags,
+    Note,
+    Kind,
+    Id,
+}
+
+impl Field {
+    pub const ALL: &'static [Field] = &[
+        Field::Rank,
+        Field::Pad,
+        Field::Title,
+        Field::Domain,
+        Field::Url,
+        Field::By,
+        Field::Age,
+        Field::Score,
+        Field::ScoreLabel,
+        Field::Comments,
+        Field::CommentsLabel,
+        Field::NewComments,
+        Field::RankChange,
+        Field::ScoreChange,
+        Field::CommentsChange,
+        Field::New,
+        Field::Bookmark,
+        Field::Tags,
+        Field::Note,
+        Field::Kind,
+        Field::Id,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Field::Rank => "rank",
+            Field::Pad => "pad",
+            Field::Title => "title",
+            Field::Domain => "domain",
+            Field::Url => "url",
+            Field::By => "by",
+            Field::Age => "age",
+            Field::Score => "score",
+            Field::ScoreLabel => "score_label",
+            Field::Comments => "comments",
+            Field::CommentsLabel => "comments_label",
+            Field::NewComments => "new_comments",
+            Field::RankChange => "rank_change",
+            Field::ScoreChange => "score_change",
+            Field::CommentsChange => "comments_change",
+            Field::New => "new",
+            Field::Bookmark => "bookmark",
+            Field::Tags => "tags",
+            Field::Note => "note",
+            Field::Kind => "type",
+            Field::Id => "id",
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Part {
+    Text(String),
+    /// A field, with the style given in the template to apply on top of its own.
+    Field(Field, Option<Style>),
+    /// Parts that are left out unless all their fields have a value.
+    Optional(Vec<Part>),
+}
+
+/// One line of the rows of the list, like `{rank} {title}[ ({domain})]`.
+///
+/// Fields are written as `{name}` or `{name:style}` with a style like `yellow,bold`. Text in
+/// square brackets is only shown if none of its fields are empty, and `\` escapes the next
+/// character, so `\[{tags}\]` shows the tags in brackets.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Template {
+    parts: Vec<Part>,
+    /// The template as it was written, to write it back out.
+    source: String,
+}
+
+impl Template {
+    pub fn is_empty(&self) -> bool {
+        self.parts.is_empty()
+    }
+
+    /// Fills the template in with the value and style of every field, and returns the spans
+    /// with the index of the title span if the template has one. Text outside of the fields
+    /// gets `text_style`.
+    pub fn render<F>(&self, value: F, text_style: Style) -> (Vec<Span<'static>>, Option<usize>)
+    where
+        F: Fn(Field) -> (String, Style),
+    {
+        let mut spans = Vec::new();
+        let mut title = None;
+        render_parts(&self.parts, &value, text_style, &mut spans, &mut title);
+        (spans, title)
+    }
+}
+
+fn render_parts<F>(
+    parts: &[Part],
+    value: &F,
+    text_style: Style,
+    spans: &mut Vec<Span<'static>>,
+    title: &mut Option<usize>,
+) where
+    F: Fn(Field) -> (String, Style),
+{
+    for part in parts {
+        match part {
+            Part::Text(text) => spans.push(Span::styled(text.clone(), text_style)),
+            Part::Field(field, style) => {
+                let (content, field_style) = value(*field);
+                if *field == Field::Title {
+                    *title = Some(spans.len());
+                }
+                let style = match style {
+                    Some(style) => field_style.patch(*style),
+                    None => field_style,
+                };
+                spans.push(Span::styled(content, style));
+            }
+            Part::Optional(parts) => {
+                if all_present(parts, value) {
+                    render_parts(parts, value, text_style, spans, title);
+                }
+            }
+        }
+    }
+}
+
+/// Returns true if none of the fields outside of nested optional parts are empty.
+fn all_present<F>(parts: &[Part], value: &F) -> bool
+where
+    F: Fn(Field) -> (String, Style),
+{
+    parts.iter().all(|part| match part {
+        Part::Field(field, _) => !value(*field).0.is_empty(),
+        Part::Text(_) | Part::Optional(_) => true,
+    })
+}
+
+impl fmt::Display for Template {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.source)
+    }
+}
+
+impl FromStr for Template {
+    type Err = String;
+
+    fn from_str(source: &str) -> Result<Self, Self::Err> {
+        let mut chars = source.chars();
+        Ok(Template {
+            parts: parse_parts(&mut chars, false)?,
+            source: source.to_string(),
+        })
+    }
+}
+
+/// Parses the parts up to the end of the template, or up to the `]` closing an optional part.
+fn parse_parts(chars: &mut std::str::Chars, optional: bool) -> Result<Vec<Part>, String> {
+    let mut parts = Vec::new();
+    let mut text = String::new();
+
+    loop {
+        let char = match chars.next() {
+            Some(char) => char,
+            None if optional => return Err(String::from("[ is never closed")),
+            None => break,
+        };
+
+        match char {
+            '\\' => text.push(chars.next().unwrap_or('\\')),
+            '{' => {
+                let mut field = String::new();
+                loop {
+                    match chars.next() {
+                        Some('}') => break,
+                        Some(char) => field.push(char),
+                        None => return Err(format!("{{{} is never closed", field)),
+                    }
+                }
+                if !text.is_empty() {
+                    parts.push(Part::Text(std::mem::take(&mut text)));
+                }
+                parts.push(parse_field(&field)?);
+            }
+            '[' => {
+                if !text.is_empty() {
+                    parts.push(Part::Text(std::mem::take(&mut text)));
+                }
+                parts.push(Part::Optional(parse_parts(chars, true)?));
+            }
+            ']' if optional => break,
+            ']' => return Err(String::from("] has no matching [")),
+            '}' => return Err(String::from("} has no matching {")),
+            char => text.push(char),
+        }
+    }
+
+    if !text.is_empty() {
+        parts.push(Part::Text(text));
+    }
+
+    Ok(parts)
+}
+
+/// Parses the inside of `{name}` or `{name:style}`.
+fn parse_field(field: &str) -> Result<Part, String> {
+    let (name, style) = match field.split_once(':') {
+        Some((name, style)) => (name.trim(), Some(parse_style(style.split(','))?)),
+        None => (field.trim(), None),
+    };
+
+    let field = Field::ALL
+        .iter()
+        .copied()
+        .find(|field| field.name() == name)
+        .ok_or_else(|| format!("{} is not a valid field", name))?;
+
+    Ok(Part::Field(field, style))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tui::style::{Color, Modifier};
+
+    /// Renders the template to plain text, with the fields in `values` filled in and the
+    /// other fields left empty.
+    fn render(template: &str, values: &[(Field, &str)]) -> String {
+        let template: Template = template.parse().unwrap();
+        let value = |field| {
+            let value = values
+                .iter()
+                .find(|(named, _)| *named == field)
+                .map_or("", |(_, value)| value);
+            (value.to_string(), Style::default())
+        };
+        let (spans, _) = template.render(value, Style::default());
+        spans.iter().map(|span| span.content.as_ref()).collect()
+    }
+
+    #[test]
+    fn parses_the_defaults() {
+        for line in DEFAULT_LINES.iter().chain(&[DEFAULT_COMPACT]) {
+            let template: Template = line.parse().unwrap();
+            assert_eq!(template.to_string(), *line);
+        }
+        assert!("".parse::<Template>().unwrap().is_empty());
+    }
+
+    #[test]
+    fn leaves_out_optional_parts_with_empty_fields() {
+        let template = "{rank} {title}[ ({domain})][ by {by} | {age}]";
+        assert_eq!(
+            render(template, &[(Field::Rank, "1"), (Field::Title, "Rust")]),
+            "1 Rust"
+        );
+        assert_eq!(
+            render(
+                template,
+                &[
+                    (Field::Rank, "1"),
+                    (Field::Title, "Rust"),
+                    (Field::Domain, "rust-lang.org"),
+                    (Field::By, "pg"),
+                ]
+            ),
+            "1 Rust (rust-lang.org)"
+        );
+    }
+
+    #[test]
+    fn shows_an_optional_part_whatever_its_nested_parts_are() {
+        let template = "{title}[ {score}[ +{score_change}]]";
+        assert_eq!(
+            render(template, &[(Field::Title, "Rust"), (Field::Score, "5")]),
+            "Rust 5"
+        );
+        assert_eq!(
+            render(
+                template,
+                &[
+                    (Field::Title, "Rust"),
+                    (Field::Score, "5"),
+                    (Field::ScoreChange, "2")
+                ]
+            ),
+            "Rust 5 +2"
+        );
+        assert_eq!(
+            render(
+                template,
+                &[(Field::Title, "Rust"), (Field::ScoreChange, "2")]
+            ),
+            "Rust"
+        );
+    }
+
+    #[test]
+    fn escapes_brackets() {
+        assert_eq!(
+            render(
+                r"{title}[ \[{tags}\]]",
+                &[(Field::Title, "Rust"), (Field::Tags, "a")]
+            ),
+            "Rust [a]"
+        );
+        assert_eq!(
+            render(r"{title}[ \[{tags}\]]", &[(Field::Title, "Rust")]),
+            "Rust"
+        );
+    }
+
+    #[test]
+    fn styles_fields_and_finds_the_title() {
+        let template: Template = "{rank} {title:yellow,bold}".parse().unwrap();
+        let (spans, title) =
+            template.render(|_| (String::from("x"), Style::default()), Style::default());
+        assert_eq!(title, Some(2));
+        assert_eq!(
+            spans[2].style,
+            Style::default()
+                .fg(Color::Yellow)
+                .add_modifier(Modifier::BOLD)
+        );
+    }
+
+    #[test]
+    fn reports_invalid_templates() {
+        let error = |template: &str| template.parse::<Template>().unwrap_err();
+        assert_eq!(error("{rank}[ {title}"), "[ is never closed");
+        assert_eq!(error("{rank"), "{rank is never closed");
+        assert_eq!(error("{rank}]"), "] has no matching [");
+        assert_eq!(error("rank}"), "} has no matching {");
+        assert_eq!(error("{nope}"), "nope is not a valid field");
+        assert!(error("{title:nocolor}").contains("nocolor"));
+    }
+}
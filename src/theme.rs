@@ -0,0 +1,223 @@
+use crate::{config::split_list, ui::parse_style};
+use tui::style::{Color, Modifier, Style};
+
+/// The styles of every element of the TUI, set with the `[theme]` section of the config.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Theme {
+    pub rank: Style,
+    pub title: Style,
+    pub domain: Style,
+    /// The points, author, age and comment count of a post.
+    pub metadata: Style,
+    pub selected: Style,
+    pub tab: Style,
+    pub active_tab: Style,
+    /// The marker of posts that weren't in the list before the last refresh.
+    pub new: Style,
+    /// Posts that rose in rank, or gained points or comments, in the last refresh.
+    pub up: Style,
+    /// Posts that fell in rank in the last refresh.
+    pub down: Style,
+    pub bookmark: Style,
+    pub tags: Style,
+    pub note: Style,
+    /// Posts whose article or comments were opened before.
+    pub visited: Style,
+    pub status: Style,
+    pub error: Style,
+    pub status_bar: Style,
+    /// The keys shown in the status bar and the help.
+    pub key: Style,
+    /// The completion shown in the command palette.
+    pub hint: Style,
+}
+
+impl std::default::Default for Theme {
+    fn default() -> Self {
+        Theme {
+            rank: Style::default().fg(Color::Red),
+            title: Style::default(),
+            domain: Style::default().add_modifier(Modifier::ITALIC),
+            metadata: Style::default().fg(Color::LightBlue),
+            selected: Style::default().fg(Color::Green),
+            tab: Style::default().fg(Color::White),
+            active_tab: Style::default().fg(Color::Yellow),
+            new: Style::default().fg(Color::LightGreen),
+            up: Style::default().fg(Color::Green),
+            down: Style::default().fg(Color::Red),
+            bookmark: Style::default().fg(Color::Yellow),
+            tags: Style::default().fg(Color::Cyan),
+            note: Style::default().fg(Color::Gray),
+            visited: Style::default().add_modifier(Modifier::DIM),
+            status: Style::default().fg(Color::Yellow),
+            error: Style::default().fg(Color::LightRed),
+            status_bar: Style::default().bg(Color::Indexed(236)),
+            key: Style::default().fg(Color::Yellow),
+            hint: Style::default().add_modifier(Modifier::DIM),
+        }
+    }
+}
+
+impl Theme {
+    /// Returns one of the built-in themes: default, light, solarized or monochrome.
+    pub fn named(name: &str) -> Option<Theme> {
+        match name {
+            "default" => Some(Theme::default()),
+            "light" => Some(Theme::light()),
+            "solarized" => Some(Theme::solarized()),
+            "monochrome" => Some(Theme::monochrome()),
+            _ => None,
+        }
+    }
+
+    /// Returns true if the `NO_COLOR` environment variable asks for output without colors.
+    pub fn no_color() -> bool {
+        std::env::var_os("NO_COLOR").is_some_and(|value| !value.is_empty())
+    }
+
+    /// Returns the style of every element, by the name it's set with.
+    pub fn elements(&self) -> [(&'static str, Style); 19] {
+        [
+            ("rank", self.rank),
+            ("title", self.title),
+            ("domain", self.domain),
+            ("metadata", self.metadata),
+            ("selected", self.selected),
+            ("tab", self.tab),
+            ("active_tab", self.active_tab),
+            ("new", self.new),
+            ("up", self.up),
+            ("down", self.down),
+            ("bookmark", self.bookmark),
+            ("tags", self.tags),
+            ("note", self.note),
+            ("visited", self.visited),
+            ("status", self.status),
+            ("error", self.error),
+            ("status_bar", self.status_bar),
+            ("key", self.key),
+            ("hint", self.hint),
+        ]
+    }
+
+    /// Sets the style of an element from a value like `red, bold` or `#268bd2, bg:236`.
+    pub fn set(&mut self, element: &str, value: &str) -> Result<(), String> {
+        let field = match element {
+            "rank" => &mut self.rank,
+            "title" => &mut self.title,
+            "domain" => &mut self.domain,
+            "metadata" => &mut self.metadata,
+            "selected" => &mut self.selected,
+            "tab" => &mut self.tab,
+            "active_tab" => &mut self.active_tab,
+            "new" => &mut self.new,
+            "up" => &mut self.up,
+            "down" => &mut self.down,
+            "bookmark" => &mut self.bookmark,
+            "tags" => &mut self.tags,
+            "note" => &mut self.note,
+            "visited" => &mut self.visited,
+            "status" => &mut self.status,
+            "error" => &mut self.error,
+            "status_bar" => &mut self.status_bar,
+            "key" => &mut self.key,
+            "hint" => &mut self.hint,
+            _ => return Err(String::from("unknown theme element")),
+        };
+
+        *field = parse_style(split_list(value)?.iter().map(String::as_str))?;
+        Ok(())
+    }
+
+    /// Colors that stay readable on a light background.
+    fn light() -> Theme {
+        Theme {
+            rank: Style::default().fg(Color::Red),
+            title: Style::default(),
+            domain: Style::default()
+                .fg(Color::DarkGray)
+                .add_modifier(Modifier::ITALIC),
+            metadata: Style::default().fg(Color::Blue),
+            selected: Style::default()
+                .fg(Color::Blue)
+                .add_modifier(Modifier::BOLD),
+            tab: Style::default().fg(Color::Black),
+            active_tab: Style::default()
+                .fg(Color::Magenta)
+                .add_modifier(Modifier::BOLD),
+            new: Style::default().fg(Color::Green),
+            up: Style::default().fg(Color::Green),
+            down: Style::default().fg(Color::Red),
+            bookmark: Style::default().fg(Color::Indexed(130)),
+            tags: Style::default().fg(Color::Blue),
+            note: Style::default().fg(Color::DarkGray),
+            visited: Style::default().add_modifier(Modifier::DIM),
+            status: Style::default().fg(Color::Red),
+            error: Style::default().fg(Color::Red).add_modifier(Modifier::BOLD),
+            status_bar: Style::default().bg(Color::Indexed(254)),
+            key: Style::default().fg(Color::Magenta),
+            hint: Style::default().fg(Color::DarkGray),
+        }
+    }
+
+    /// The accent colors of the Solarized palette.
+    fn solarized() -> Theme {
+        let base02 = Color::Rgb(0x07, 0x36, 0x42);
+        let base01 = Color::Rgb(0x58, 0x6e, 0x75);
+        let base00 = Color::Rgb(0x65, 0x7b, 0x83);
+        let base1 = Color::Rgb(0x93, 0xa1, 0xa1);
+        let yellow = Color::Rgb(0xb5, 0x89, 0x00);
+        let orange = Color::Rgb(0xcb, 0x4b, 0x16);
+        let red = Color::Rgb(0xdc, 0x32, 0x2f);
+        let blue = Color::Rgb(0x26, 0x8b, 0xd2);
+        let cyan = Color::Rgb(0x2a, 0xa1, 0x98);
+        let green = Color::Rgb(0x85, 0x99, 0x00);
+
+        Theme {
+            rank: Style::default().fg(red),
+            title: Style::default(),
+            domain: Style::default().fg(base01).add_modifier(Modifier::ITALIC),
+            metadata: Style::default().fg(blue),
+            selected: Style::default().fg(green).add_modifier(Modifier::BOLD),
+            tab: Style::default().fg(base1),
+            active_tab: Style::default().fg(yellow).add_modifier(Modifier::BOLD),
+            new: Style::default().fg(green),
+            up: Style::default().fg(green),
+            down: Style::default().fg(red),
+            bookmark: Style::default().fg(yellow),
+            tags: Style::default().fg(cyan),
+            note: Style::default().fg(base00),
+            visited: Style::default().add_modifier(Modifier::DIM),
+            status: Style::default().fg(orange),
+            error: Style::default().fg(red),
+            status_bar: Style::default().fg(base1).bg(base02),
+            key: Style::default().fg(blue),
+            hint: Style::default().fg(base01),
+        }
+    }
+
+    /// No colors at all, only modifiers. Used when `NO_COLOR` is set.
+    fn monochrome() -> Theme {
+        Theme {
+            rank: Style::default().add_modifier(Modifier::BOLD),
+            title: Style::default(),
+            domain: Style::default().add_modifier(Modifier::ITALIC),
+            metadata: Style::default(),
+            selected: Style::default().add_modifier(Modifier::REVERSED),
+            tab: Style::default(),
+            active_tab: Style::default().add_modifier(Modifier::BOLD | Modifier::UNDERLINED),
+            new: Style::default().add_modifier(Modifier::BOLD),
+            up: Style::default(),
+            down: Style::default(),
+            bookmark: Style::default().add_modifier(Modifier::BOLD),
+            tags: Style::default(),
+            note: Style::default().add_modifier(Modifier::ITALIC),
+            visited: Style::default().add_modifier(Modifier::DIM),
+            status: Style::default().add_modifier(Modifier::BOLD),
+            error: Style::default().add_modifier(Modifier::BOLD),
+            status_bar: Style::default().add_modifier(Modifier::REVERSED),
+            key: Style::default().add_modifier(Modifier::BOLD),
+            hint: Style::default().add_modifier(Modifier::DIM),
+        }
+    }
+}
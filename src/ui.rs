@@ -1,36 +1,202 @@
+use crossterm::event::KeyCode;
+use serde::{Deserialize, Serialize};
+use std::fmt;
 use tui::{
-    text::Text,
-    widgets::{ListItem, ListState},
+    layout::Rect,
+    style::{Color, Modifier, Style},
+    text::Span,
+    widgets::ListState,
 };
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
 
-#[derive(Clone, Debug, PartialEq)]
-pub struct PostItem<'a> {
-    pub top_item: ListItem<'a>,
-    pub bottom_item: ListItem<'a>,
+/// Terminals narrower than this show every post on a single row.
+const COMPACT_WIDTH: u16 = 80;
+/// Terminals at least this wide show a preview of the selected post next to the list.
+const WIDE_WIDTH: u16 = 140;
+/// Split views at least this wide put their panes side by side instead of stacking them.
+const SIDE_BY_SIDE_WIDTH: u16 = 140;
+/// Titles aren't shortened to less than this many columns to make room for the rest of a row.
+const MIN_TITLE_WIDTH: usize = 12;
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum LayoutMode {
+    /// Automatically picks one of the other modes based on the width of the terminal.
+    Auto,
+    /// A single row per post.
+    Compact,
+    /// A row with the title and a row with the details of every post.
+    Normal,
+    /// The normal list with a preview of the selected post next to it.
+    Wide,
+}
+
+impl std::str::FromStr for LayoutMode {
+    type Err = String;
+
+    fn from_str(layout: &str) -> Result<Self, Self::Err> {
+        match layout.to_lowercase().as_str() {
+            "auto" => Ok(LayoutMode::Auto),
+            "compact" => Ok(LayoutMode::Compact),
+            "normal" => Ok(LayoutMode::Normal),
+            "wide" => Ok(LayoutMode::Wide),
+            _ => Err(format!(
+                "{} is not a valid layout, expected auto, compact, normal or wide",
+                layout
+            )),
+        }
+    }
 }
 
-impl<'a> From<PostItem<'a>> for Vec<ListItem<'a>> {
-    fn from(post_item: PostItem<'a>) -> Self {
-        vec![post_item.top_item, post_item.bottom_item]
+impl fmt::Display for LayoutMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            LayoutMode::Auto => "auto",
+            LayoutMode::Compact => "compact",
+            LayoutMode::Normal => "normal",
+            LayoutMode::Wide => "wide",
+        })
     }
 }
 
-impl<'a> PostItem<'a> {
-    pub fn new<T>(content_top: T, content_bottom: T) -> PostItem<'a>
-    where
-        T: Into<Text<'a>>,
-    {
-        PostItem {
-            top_item: ListItem::new(content_top),
-            bottom_item: ListItem::new(content_bottom),
+impl LayoutMode {
+    /// Resolves `Auto` to the mode for a terminal of the width.
+    pub fn for_width(self, width: u16) -> LayoutMode {
+        match self {
+            LayoutMode::Auto if width < COMPACT_WIDTH => LayoutMode::Compact,
+            LayoutMode::Auto if width >= WIDE_WIDTH => LayoutMode::Wide,
+            LayoutMode::Auto => LayoutMode::Normal,
+            mode => mode,
         }
     }
 }
 
+/// How the two panes of the split view are arranged.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum SplitLayout {
+    /// Side by side on wide terminals, stacked otherwise.
+    Auto,
+    SideBySide,
+    Stacked,
+}
+
+impl std::str::FromStr for SplitLayout {
+    type Err = String;
+
+    fn from_str(layout: &str) -> Result<Self, Self::Err> {
+        match layout.to_lowercase().as_str() {
+            "auto" => Ok(SplitLayout::Auto),
+            "side_by_side" => Ok(SplitLayout::SideBySide),
+            "stacked" => Ok(SplitLayout::Stacked),
+            _ => Err(format!(
+                "{} is not a valid split_layout, expected auto, side_by_side or stacked",
+                layout
+            )),
+        }
+    }
+}
+
+impl fmt::Display for SplitLayout {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            SplitLayout::Auto => "auto",
+            SplitLayout::SideBySide => "side_by_side",
+            SplitLayout::Stacked => "stacked",
+        })
+    }
+}
+
+impl SplitLayout {
+    /// Resolves `Auto` to the arrangement for a terminal of the width.
+    pub fn for_width(self, width: u16) -> SplitLayout {
+        match self {
+            SplitLayout::Auto if width >= SIDE_BY_SIDE_WIDTH => SplitLayout::SideBySide,
+            SplitLayout::Auto => SplitLayout::Stacked,
+            layout => layout,
+        }
+    }
+}
+
+/// Shortens the text to fit in the width, ending it with an ellipsis if anything was cut.
+pub fn truncate(text: &str, width: usize) -> String {
+    if text.width() <= width {
+        return text.to_string();
+    }
+
+    let mut truncated = String::new();
+    let mut used = 0;
+    for char in text.chars() {
+        let char_width = char.width().unwrap_or(0);
+        if used + char_width + 1 > width {
+            break;
+        }
+        used += char_width;
+        truncated.push(char);
+    }
+
+    truncated.push('…');
+    truncated
+}
+
+/// Breaks the text into lines of at most the width, between words where possible. Empty
+/// lines are kept, to separate paragraphs.
+pub fn wrap(text: &str, width: usize) -> Vec<String> {
+    let width = width.max(1);
+    let mut lines = Vec::new();
+
+    for paragraph in text.lines() {
+        // The indentation of the first line is kept, for code.
+        let indent = paragraph.len() - paragraph.trim_start().len();
+        let mut line = " ".repeat(indent.min(width / 2));
+        for word in paragraph.split_whitespace() {
+            if !line.trim_start().is_empty() && line.width() + 1 + word.width() > width {
+                lines.push(std::mem::take(&mut line));
+            }
+            if !line.trim_start().is_empty() {
+                line.push(' ');
+            }
+
+            // Words longer than the line, like urls, are broken up.
+            for char in word.chars() {
+                if !line.is_empty() && line.width() + char.width().unwrap_or(0) > width {
+                    lines.push(std::mem::take(&mut line));
+                }
+                line.push(char);
+            }
+        }
+        lines.push(line);
+    }
+
+    lines
+}
+
+/// Shortens the span at `index` so the spans fit in the width, keeping the spans after it,
+/// like the domain after a title, visible. The span is never made shorter than
+/// `MIN_TITLE_WIDTH`.
+pub fn fit_span(spans: &mut [Span<'static>], index: usize, width: usize) {
+    let total: usize = spans.iter().map(|span| span.content.width()).sum();
+    if total <= width || index >= spans.len() {
+        return;
+    }
+
+    let span = &mut spans[index];
+    let span_width = span.content.width();
+    let target = span_width
+        .saturating_sub(total - width)
+        .max(MIN_TITLE_WIDTH)
+        .min(span_width);
+    span.content = truncate(&span.content, target).into();
+}
+
+/// A list where every post takes up `rows` items, which are selected together by selecting
+/// the first one.
 pub struct StatefulList<T> {
     pub state: ListState,
     pub items: Vec<T>,
     scroll_past_list: bool,
+    rows: usize,
+    /// The index of the first visible item. `ListState` keeps its own copy private, so the
+    /// list is rendered from this offset instead, which can be restored.
+    offset: usize,
 }
 
 impl<T> StatefulList<T>
@@ -42,24 +208,127 @@ where
             state: ListState::default(),
             items,
             scroll_past_list,
+            rows: 2,
+            offset: 0,
+        }
+    }
+
+    /// Scrolls the list the same way the `List` widget does when it is rendered with the
+    /// height, so `item_at` knows which item is on which row.
+    pub fn update_offset(&mut self, height: usize) {
+        if self.items.is_empty() || height == 0 {
+            return;
+        }
+
+        let selected = self.state.selected().unwrap_or(0).min(self.items.len() - 1);
+        let end = (self.offset + height)
+            .min(self.items.len())
+            .max(self.offset);
+        if selected >= end {
+            self.offset = self.offset.max((selected + 1).saturating_sub(height));
+        } else if selected < self.offset {
+            self.offset = selected;
+        }
+    }
+
+    /// Returns the items that fit in the height from the offset, and the state to render them
+    /// with. Call `update_offset` with the same height first.
+    pub fn visible(&self, height: usize) -> (Vec<T>, ListState) {
+        let items = self
+            .items
+            .iter()
+            .skip(self.offset)
+            .take(height)
+            .cloned()
+            .collect();
+        let mut state = ListState::default();
+        state.select(
+            self.state
+                .selected()
+                .and_then(|selected| selected.checked_sub(self.offset)),
+        );
+        (items, state)
+    }
+
+    /// Returns the row the selected item is shown on, counted from the top of the list.
+    pub fn selected_row(&self) -> Option<usize> {
+        self.state
+            .selected()
+            .map(|selected| selected.saturating_sub(self.offset))
+    }
+
+    /// Selects the post and scrolls the list to show it on the row, as far as the height of
+    /// the list allows when it's drawn.
+    pub fn select_post_at_row(&mut self, post: usize, row: usize) {
+        self.select_post(post);
+        self.offset = self
+            .state
+            .selected()
+            .map_or(0, |selected| selected.saturating_sub(row));
+    }
+
+    /// Returns the index of the item on the row, counted from the top of the list.
+    pub fn item_at(&self, row: usize) -> Option<usize> {
+        let index = self.offset + row;
+        if index < self.items.len() {
+            Some(index)
+        } else {
+            None
+        }
+    }
+
+    /// Returns the number of posts that fit in the height.
+    pub fn page_size(&self, height: usize) -> usize {
+        (height / self.rows).max(1)
+    }
+
+    /// Returns the position of the post the item belongs to.
+    pub fn post_at(&self, index: usize) -> usize {
+        index / self.rows
+    }
+
+    /// Returns the position of the selected post, which can be past the last post if
+    /// `scroll_past_list` is set.
+    pub fn selected_post(&self) -> Option<usize> {
+        self.state.selected().map(|index| self.post_at(index))
+    }
+
+    pub fn set_scroll_past_list(&mut self, scroll_past_list: bool) {
+        self.scroll_past_list = scroll_past_list;
+    }
+
+    /// Replaces the items of the list, which has `rows` items for every post, keeping the
+    /// same post selected and the selection within bounds.
+    pub fn set_items(&mut self, items: Vec<T>, rows: usize) {
+        if rows != self.rows {
+            let selected = self.selected_post();
+            self.offset = self.offset / self.rows * rows;
+            self.rows = rows;
+            self.state.select(selected.map(|post| post * rows));
+        }
+        self.items = items;
+
+        match self.state.selected() {
+            Some(_) if self.items.is_empty() => self.state.select(None),
+            Some(i) if i >= self.items.len() => {
+                self.state.select(Some(self.items.len() - self.rows))
+            }
+            None if !self.items.is_empty() => self.state.select(Some(0)),
+            _ => {}
         }
     }
 
     pub fn next(&mut self) {
+        if self.items.is_empty() {
+            return;
+        }
+
         let i = match self.state.selected() {
             Some(i) => {
-                if self.scroll_past_list {
-                    if i >= self.items.len() - 1 {
-                        0
-                    } else {
-                        i + 2
-                    }
+                if i >= self.last_index() {
+                    0
                 } else {
-                    if i >= self.items.len() - 2 {
-                        0
-                    } else {
-                        i + 2
-                    }
+                    i + self.rows
                 }
             }
             None => 0,
@@ -68,17 +337,58 @@ where
         self.state.select(Some(i));
     }
 
+    /// Moves the selection by a number of posts, stopping at the first and last post instead
+    /// of wrapping around.
+    pub fn move_by(&mut self, posts: isize) {
+        if self.items.is_empty() {
+            return;
+        }
+
+        let current = self.state.selected().unwrap_or(0) as isize;
+        let target = (current + posts * self.rows as isize).clamp(0, self.last_index() as isize);
+        self.state.select(Some(target as usize));
+    }
+
+    /// Selects the post at the position in the list, or the last post if it's out of bounds.
+    pub fn select_post(&mut self, post: usize) {
+        if self.items.is_empty() {
+            return;
+        }
+
+        let last = self.items.len() - self.rows;
+        self.state
+            .select(Some(post.saturating_mul(self.rows).min(last)));
+    }
+
+    pub fn first(&mut self) {
+        self.select_post(0);
+    }
+
+    pub fn last(&mut self) {
+        self.select_post(usize::MAX);
+    }
+
+    /// The highest index that can be selected, which is past the last post if
+    /// `scroll_past_list` is set.
+    fn last_index(&self) -> usize {
+        if self.scroll_past_list {
+            self.items.len()
+        } else {
+            self.items.len() - self.rows
+        }
+    }
+
     pub fn previous(&mut self) {
+        if self.items.is_empty() {
+            return;
+        }
+
         let i = match self.state.selected() {
             Some(i) => {
                 if i == 0 {
-                    if self.scroll_past_list {
-                        self.items.len()
-                    } else {
-                        self.items.len() - 2
-                    }
+                    self.last_index()
                 } else {
-                    i - 2
+                    i - self.rows
                 }
             }
             None => 0,
@@ -88,10 +398,29 @@ where
     }
 }
 
-#[derive(Copy, Clone, Debug)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
 pub enum MenuItem {
     Top,
     New,
+    Ask,
+    Show,
+    Jobs,
+    Saved,
+}
+
+impl MenuItem {
+    pub const ALL: [MenuItem; 6] = [
+        MenuItem::Top,
+        MenuItem::New,
+        MenuItem::Ask,
+        MenuItem::Show,
+        MenuItem::Jobs,
+        MenuItem::Saved,
+    ];
+
+    /// The names of the tabs, for messages about a tab that doesn't exist.
+    pub const NAMES: &'static str = "top, new, ask, show, jobs or saved";
 }
 
 impl From<MenuItem> for usize {
@@ -99,19 +428,271 @@ impl From<MenuItem> for usize {
         match input {
             MenuItem::Top => 0,
             MenuItem::New => 1,
+            MenuItem::Ask => 2,
+            MenuItem::Show => 3,
+            MenuItem::Jobs => 4,
+            MenuItem::Saved => 5,
+        }
+    }
+}
+
+impl fmt::Display for MenuItem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            MenuItem::Top => "Top",
+            MenuItem::New => "New",
+            MenuItem::Ask => "Ask",
+            MenuItem::Show => "Show",
+            MenuItem::Jobs => "Jobs",
+            MenuItem::Saved => "Saved",
+        })
+    }
+}
+
+impl std::str::FromStr for MenuItem {
+    type Err = String;
+
+    fn from_str(tab: &str) -> Result<Self, Self::Err> {
+        match tab.to_lowercase().as_str() {
+            "top" => Ok(MenuItem::Top),
+            "new" => Ok(MenuItem::New),
+            "ask" => Ok(MenuItem::Ask),
+            "show" => Ok(MenuItem::Show),
+            "jobs" => Ok(MenuItem::Jobs),
+            "saved" => Ok(MenuItem::Saved),
+            _ => Err(format!(
+                "{} is not a valid tab, expected {}",
+                tab,
+                MenuItem::NAMES
+            )),
         }
     }
 }
 
 impl MenuItem {
     pub fn scroll(&mut self) {
-        match *self {
-            MenuItem::Top => {
-                *self = MenuItem::New;
-            }
-            MenuItem::New => {
-                *self = MenuItem::Top;
+        *self = MenuItem::ALL[(usize::from(*self) + 1) % MenuItem::ALL.len()];
+    }
+
+    pub fn scroll_back(&mut self) {
+        let len = MenuItem::ALL.len();
+        *self = MenuItem::ALL[(usize::from(*self) + len - 1) % len];
+    }
+}
+
+/// Returns an area of at most the given size in the middle of `area`.
+pub fn centered(area: Rect, width: u16, height: u16) -> Rect {
+    let width = width.min(area.width);
+    let height = height.min(area.height);
+    Rect {
+        x: area.x + (area.width - width) / 2,
+        y: area.y + (area.height - height) / 2,
+        width,
+        height,
+    }
+}
+
+/// Returns the index of the tab on the column, laid out the same way as the `Tabs` widget does
+/// in `area`, with a single character divider.
+pub fn tab_at(titles: &[String], area: Rect, column: u16) -> Option<usize> {
+    let mut x = area.left();
+    for (index, title) in titles.iter().enumerate() {
+        let start = x;
+        let end = x + 1 + title.chars().count() as u16;
+        if column >= start && column <= end {
+            return Some(index);
+        }
+        x = end + 2;
+    }
+
+    None
+}
+
+/// A single line text input shown at the bottom of the screen.
+pub struct Prompt<T> {
+    pub kind: T,
+    pub label: &'static str,
+    pub input: String,
+}
+
+impl<T> Prompt<T> {
+    pub fn new(kind: T, label: &'static str, input: String) -> Prompt<T> {
+        Prompt { kind, label, input }
+    }
+
+    /// Edits the input with the key. Enter and escape are left to the caller.
+    pub fn handle_key(&mut self, code: KeyCode) {
+        match code {
+            KeyCode::Backspace => {
+                self.input.pop();
             }
+            KeyCode::Char(c) => self.input.push(c),
+            _ => {}
+        }
+    }
+}
+
+/// Parses a named color, a 256 color palette index like `208` or an RGB color like `#ff8800`.
+pub fn parse_color(color: &str) -> Option<Color> {
+    if let Ok(index) = color.parse::<u8>() {
+        return Some(Color::Indexed(index));
+    }
+
+    if let Some(hex) = color.strip_prefix('#') {
+        if hex.len() != 6 || !hex.is_ascii() {
+            return None;
+        }
+        let channel = |range| u8::from_str_radix(&hex[range], 16).ok();
+        return Some(Color::Rgb(channel(0..2)?, channel(2..4)?, channel(4..6)?));
+    }
+
+    COLOR_NAMES
+        .iter()
+        .find(|(name, _)| *name == color)
+        .map(|(_, color)| *color)
+}
+
+const COLOR_NAMES: &[(&str, Color)] = &[
+    ("reset", Color::Reset),
+    ("black", Color::Black),
+    ("red", Color::Red),
+    ("green", Color::Green),
+    ("yellow", Color::Yellow),
+    ("blue", Color::Blue),
+    ("magenta", Color::Magenta),
+    ("cyan", Color::Cyan),
+    ("gray", Color::Gray),
+    ("dark_gray", Color::DarkGray),
+    ("light_red", Color::LightRed),
+    ("light_green", Color::LightGreen),
+    ("light_yellow", Color::LightYellow),
+    ("light_blue", Color::LightBlue),
+    ("light_magenta", Color::LightMagenta),
+    ("light_cyan", Color::LightCyan),
+    ("white", Color::White),
+];
+
+const MODIFIER_NAMES: &[(&str, Modifier)] = &[
+    ("bold", Modifier::BOLD),
+    ("dim", Modifier::DIM),
+    ("italic", Modifier::ITALIC),
+    ("underlined", Modifier::UNDERLINED),
+    ("reversed", Modifier::REVERSED),
+    ("crossed_out", Modifier::CROSSED_OUT),
+];
+
+fn format_color(color: Color) -> String {
+    match color {
+        Color::Indexed(index) => index.to_string(),
+        Color::Rgb(red, green, blue) => format!("#{:02x}{:02x}{:02x}", red, green, blue),
+        color => COLOR_NAMES
+            .iter()
+            .find(|(_, named)| *named == color)
+            .map(|(name, _)| name.to_string())
+            .unwrap_or_default(),
+    }
+}
+
+/// Writes the style in the syntax `parse_style` reads, like `yellow, bg:black, bold`.
+pub fn format_style(style: Style) -> String {
+    let mut attributes = Vec::new();
+    if let Some(color) = style.fg {
+        attributes.push(format_color(color));
+    }
+    if let Some(color) = style.bg {
+        attributes.push(format!("bg:{}", format_color(color)));
+    }
+    for (name, modifier) in MODIFIER_NAMES {
+        if style.add_modifier.contains(*modifier) {
+            attributes.push(name.to_string());
+        }
+    }
+
+    attributes.join(", ")
+}
+
+/// Parses a list of style attributes like `["yellow", "bg:black", "bold"]` into a `Style`.
+/// A bare color sets the foreground, a color prefixed with `bg:` sets the background.
+pub fn parse_style<'a, I>(attributes: I) -> Result<Style, String>
+where
+    I: IntoIterator<Item = &'a str>,
+{
+    let mut style = Style::default();
+
+    for attribute in attributes {
+        let attribute = attribute.trim().to_lowercase();
+        if let Some((_, modifier)) = MODIFIER_NAMES.iter().find(|(name, _)| *name == attribute) {
+            style = style.add_modifier(*modifier);
+            continue;
         }
+
+        style = if let Some(background) = attribute.strip_prefix("bg:") {
+            match parse_color(background) {
+                Some(color) => style.bg(color),
+                None => return Err(format!("{} is not a valid color", background)),
+            }
+        } else {
+            match parse_color(attribute.strip_prefix("fg:").unwrap_or(&attribute)) {
+                Some(color) => style.fg(color),
+                None => return Err(format!("{} is not a valid style", attribute)),
+            }
+        };
+    }
+
+    Ok(style)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn truncates_to_the_display_width() {
+        assert_eq!(truncate("hello world", 20), "hello world");
+        assert_eq!(truncate("hello world", 8), "hello w…");
+        // Wide characters take two columns, so fewer of them fit.
+        assert_eq!(truncate("日本語テキスト", 14), "日本語テキスト");
+        assert_eq!(truncate("日本語テキスト", 6), "日本…");
+        assert_eq!(truncate("日本語テキスト", 7), "日本語…");
+        assert_eq!(truncate("🦀🦀🦀", 4), "🦀…");
+        assert!(truncate("日本語テキスト", 6).width() <= 6);
+    }
+
+    #[test]
+    fn fits_the_title_and_keeps_the_spans_after_it() {
+        let mut spans = vec![
+            Span::raw("1. "),
+            Span::raw("日本語のタイトル"),
+            Span::raw(" (example.com)"),
+        ];
+        fit_span(&mut spans, 1, 30);
+        assert_eq!(spans[1].content, "日本語のタイ…");
+        assert_eq!(spans[2].content, " (example.com)");
+        assert_eq!(
+            spans.iter().map(|span| span.content.width()).sum::<usize>(),
+            30
+        );
+
+        // The title keeps its minimum width, even if the line overflows.
+        fit_span(&mut spans, 1, 10);
+        assert_eq!(spans[1].content.width(), MIN_TITLE_WIDTH - 1);
+    }
+
+    #[test]
+    fn wraps_to_the_display_width() {
+        assert_eq!(wrap("a b c", 3), ["a b", "c"]);
+        assert_eq!(wrap("日本語 テキスト", 8), ["日本語", "テキスト"]);
+        // Words wider than the line are broken between characters, never inside one.
+        let lines = wrap("日本語テキスト", 5);
+        assert_eq!(lines, ["日本", "語テ", "キス", "ト"]);
+        assert!(lines.iter().all(|line| line.width() <= 5));
+        assert_eq!(wrap("🦀🦀🦀 ok", 4), ["🦀🦀", "🦀", "ok"]);
+    }
+
+    #[test]
+    fn wraps_paragraphs_and_keeps_indentation() {
+        assert_eq!(wrap("one\n\ntwo", 10), ["one", "", "two"]);
+        assert_eq!(wrap("    let x = 1;", 20), ["    let x = 1;"]);
+        assert_eq!(wrap("", 0), Vec::<String>::new());
     }
 }